@@ -0,0 +1,100 @@
+use std::{cell::Cell, collections::VecDeque, future::Future, pin::pin, rc::Rc, task::{Context, Poll, RawWaker, RawWakerVTable, Waker}};
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use m5stack_avatar_rs::{Avatar, AsyncTimer, BasicPaletteKey, Command, CommandSource, Expression, Instant, Palette, Timer, components::face::DrawContext};
+
+/// Virtual clock which jumps straight to the deadline instead of sleeping.
+struct MockClock {
+    now: Rc<Cell<u64>>,
+}
+
+impl Timer for MockClock {
+    fn timestamp_milliseconds(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+impl AsyncTimer for MockClock {
+    async fn wait_until(&mut self, deadline: Instant) {
+        let now = self.now();
+        if deadline.is_after(&now) {
+            self.now.set(self.now.get() + deadline.milliseconds_since(&now) as u64);
+        }
+    }
+}
+
+/// Commands which become available at the given timestamps of the mock clock.
+struct ScriptedCommands {
+    now: Rc<Cell<u64>>,
+    script: VecDeque<(u64, Command<String>)>,
+}
+
+impl CommandSource<String> for ScriptedCommands {
+    fn receive(&mut self) -> impl Future<Output = Command<String>> {
+        std::future::poll_fn(|_| match self.script.front() {
+            Some((at, _)) if *at <= self.now.get() => Poll::Ready(self.script.pop_front().unwrap().1),
+            _ => Poll::Pending,
+        })
+    }
+}
+
+/// Draw target which only counts the drawn pixels.
+struct CountingDisplay {
+    pixels: usize,
+}
+
+impl OriginDimensions for CountingDisplay {
+    fn size(&self) -> Size {
+        Size::new(320, 240)
+    }
+}
+
+impl DrawTarget for CountingDisplay {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+    fn draw_iter<I: IntoIterator<Item = Pixel<Self::Color>>>(&mut self, pixels: I) -> Result<(), Self::Error> {
+        self.pixels += pixels.into_iter().count();
+        Ok(())
+    }
+}
+
+/// Waker which does nothing, as `block_on` polls in a loop anyway.
+fn noop_waker() -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+    const RAW: RawWaker = RawWaker::new(core::ptr::null(), &VTABLE);
+    // SAFETY: the vtable functions never touch the data pointer.
+    unsafe { Waker::from_raw(RAW) }
+}
+
+/// Minimal local executor: polls the future until it completes.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+fn main() {
+    let now = Rc::new(Cell::new(0u64));
+    let mut context: DrawContext<BinaryColor, String> = DrawContext::default();
+    context.palette.set_color(&BasicPaletteKey::Primary, BinaryColor::On);
+    context.palette.set_color(&BasicPaletteKey::Background, BinaryColor::Off);
+    context.palette.set_color(&BasicPaletteKey::BalloonForeground, BinaryColor::On);
+    let mut avatar = Avatar::new(context, 30);
+    let mut timer = MockClock { now: now.clone() };
+    let mut commands = ScriptedCommands {
+        now: now.clone(),
+        script: VecDeque::from([
+            (1000, Command::SetExpression(Expression::Happy)),
+            (2000, Command::Say("konnichiwa".to_string())),
+            (5000, Command::Stop),
+        ]),
+    };
+    let mut display = CountingDisplay { pixels: 0 };
+    block_on(avatar.run_async(&mut display, &mut timer, &mut commands)).unwrap();
+    println!("stopped at {} ms after drawing {} pixels", now.get(), display.pixels);
+}
@@ -0,0 +1,35 @@
+use m5stack_avatar_rs::{Envelope, LipSync, LipSyncConfig};
+use rand_core::{RngCore, SeedableRng};
+
+const SAMPLE_RATE: u32 = 16000;
+/// 20 ms buffers, as a typical I2S DMA buffer.
+const BUFFER_LENGTH: usize = 320;
+
+/// Synthetic speech: a 220 Hz tone whose amplitude rises and falls like syllables, then background noise below the gate.
+fn buffer(index: usize, rng: &mut impl RngCore) -> [i16; BUFFER_LENGTH] {
+    let mut samples = [0i16; BUFFER_LENGTH];
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let t = (index * BUFFER_LENGTH + i) as f32 / SAMPLE_RATE as f32;
+        let value = if t < 1.0 {
+            let syllable = (t * 4.0 * std::f32::consts::PI).sin().max(0.0);
+            0.5 * syllable * (t * 220.0 * 2.0 * std::f32::consts::PI).sin()
+        } else {
+            0.01 * ((rng.next_u32() as f32 / u32::MAX as f32) * 2.0 - 1.0)
+        };
+        *sample = (value * 32767.0) as i16;
+    }
+    samples
+}
+
+fn main() {
+    let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(1);
+    let mut rms = LipSync::new(LipSyncConfig::new(SAMPLE_RATE));
+    let mut peak = LipSync::new(LipSyncConfig { envelope: Envelope::Peak, gain: 2.0, ..LipSyncConfig::new(SAMPLE_RATE) });
+    println!("  ms    rms   peak");
+    for index in 0..75 {
+        let samples = buffer(index, &mut rng);
+        let rms_ratio = rms.process_i16(&samples);
+        let peak_ratio = peak.process_f32(&samples.map(|sample| sample as f32 / 32768.0));
+        println!("{:4}  {:5.2}  {:5.2}  {}", index * 20, rms_ratio, peak_ratio, "#".repeat((rms_ratio * 40.0) as usize));
+    }
+}
@@ -22,6 +22,8 @@ fn main() -> Result<(), std::convert::Infallible> {
     context.palette.set_color(&BasicPaletteKey::Background, BinaryColor::Off);
     context.palette.set_color(&BasicPaletteKey::BalloonForeground, BinaryColor::On);
     context.palette.set_color(&BasicPaletteKey::BalloonBackground, BinaryColor::Off);
+    context.palette.set_color(&BasicPaletteKey::Sclera, BinaryColor::Off);
+    context.palette.set_color(&BasicPaletteKey::Pupil, BinaryColor::On);
     context.set_text(Some("hogefugapiyo"));
     let mut avatar = Avatar::new(context, 30);
     let timer = StdTimer{};
@@ -29,9 +31,8 @@ fn main() -> Result<(), std::convert::Infallible> {
         .theme(BinaryColorTheme::OledBlue)
         .build();
     let mut window = Window::new("Avatar", &output_settings);
+    display.clear(BinaryColor::Off)?;
     loop {
-        display.clear(BinaryColor::Off)?;
-
         avatar.context().expression = Expression::Doubt;
         avatar.run(&mut display, &timer)?;
         window.update(&display);
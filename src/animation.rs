@@ -1,41 +1,149 @@
-use core::{marker::PhantomData, fmt::Debug};
-use rand_core::{SeedableRng, RngCore};
+use rand_core::RngCore;
+use num_enum::IntoPrimitive;
+use variant_count::VariantCount;
 #[allow(unused)]
 use micromath::F32Ext as _;
-use crate::components::{face::{DrawContext, FaceContext, RandomGeneratorContext}, mouth::MouthContext, eye::{GazeContext, EyeContext}};
+use crate::{AnimationEvent, SpeechAnimator, SpeechConfig, Easing, EventContext, Lerp, Expression, ExpressionContext, ExpressionShape, Instant, MouthShape, Viseme};
+use crate::components::{balloon::BalloonContext, face::{FaceContext, RandomGeneratorContext}, mouth::MouthContext, eye::{GazeContext, EyeContext, EyeState}};
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct FrameCounter {
     counter: u64,
     frames_per_second: u64,
+    time: Instant,
 }
 
 impl FrameCounter {
+    pub fn new(frames_per_second: u64, time: Instant) -> Self {
+        Self {
+            counter: 0,
+            frames_per_second,
+            time,
+        }
+    }
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+    pub fn frames_per_second(&self) -> u64 {
+        self.frames_per_second
+    }
+    pub fn time(&self) -> Instant {
+        self.time
+    }
     pub fn after_milliseconds(&self, milliseconds: u64) -> Self {
         Self {
             counter: self.counter.wrapping_add(self.frames_per_second * milliseconds / 1000),
             frames_per_second: self.frames_per_second,
+            time: self.time.after_milliseconds(milliseconds),
         }
     }
     pub fn after_frames(&self, frames: u64) -> Self {
+        let fps = self.frames_per_second.max(1);
+        // Derive the elapsed time from the absolute frame numbers so that the frame period does not accumulate rounding errors.
+        let counter = self.counter.wrapping_add(frames);
+        let milliseconds = (counter as u128 * 1000 / fps as u128) as u64 - (self.counter as u128 * 1000 / fps as u128) as u64;
+        Self {
+            counter,
+            frames_per_second: self.frames_per_second,
+            time: self.time.after_milliseconds(milliseconds),
+        }
+    }
+    /// Number of frames after this one which start at or before `time`.
+    pub fn frames_until(&self, time: Instant) -> u64 {
+        let fps = self.frames_per_second.max(1) as u128;
+        let milliseconds = time.signed_milliseconds_since(&self.time);
+        if milliseconds < 0 {
+            return 0;
+        }
+        // Inverse of `after_frames`: the last frame whose absolute time, rounded down, is not after `time`.
+        let start = self.counter as u128 * 1000 / fps;
+        let last = ((start + milliseconds as u128 + 1) * fps - 1) / 1000;
+        (last - self.counter as u128) as u64
+    }
+    /// Returns a counter scheduled at the absolute timestamp `time`.
+    pub fn at(&self, time: Instant) -> Self {
+        let milliseconds = time.signed_milliseconds_since(&self.time) as i64;
+        let frames = self.frames_per_second as i64 * milliseconds / 1000;
         Self {
-            counter: self.counter.wrapping_add(frames),
+            counter: self.counter.wrapping_add(frames as u64),
             frames_per_second: self.frames_per_second,
+            time,
         }
     }
     pub fn is_after(&self, other: &FrameCounter) -> bool {
-        self.counter >= other.counter
+        self.time.is_after(&other.time)
+    }
+    /// Returns true if an animator `scheduled` at the given counter must run now. `None` means not scheduled yet.
+    pub fn is_due(&self, scheduled: Option<FrameCounter>) -> bool {
+        match scheduled {
+            Some(scheduled) => self.is_after(&scheduled),
+            None => true,
+        }
+    }
+    /// Returns the counter in `counters` which comes first when seen from `self`.
+    pub fn earliest<I: IntoIterator<Item = FrameCounter>>(&self, counters: I) -> Option<FrameCounter> {
+        counters.into_iter().min_by_key(|counter| counter.time.signed_milliseconds_since(&self.time))
     }
 }
 
 pub trait Animator<Context> {
     fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter;
+    /// Returns true once the animation has ended. Animators which run forever never finish.
+    fn is_finished(&self) -> bool {
+        false
+    }
+    /// Rewinds the animation so that it plays again from the start.
+    fn reset(&mut self) {}
+}
+
+#[cfg(feature = "alloc")]
+impl<Context, A: Animator<Context> + ?Sized> Animator<Context> for alloc::boxed::Box<A> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        (**self).next(counter, context)
+    }
+    fn is_finished(&self) -> bool {
+        (**self).is_finished()
+    }
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+}
+
+/// Requests which the avatar forwards to its root animator, e.g. from a [`crate::Command`]. The default
+/// implementations ignore them, so custom root animators only implement what they support.
+pub trait FaceControl {
+    fn look_at(&mut self, _horizontal: f32, _vertical: f32) {}
+    fn release_gaze(&mut self) {}
+    fn force_blink(&mut self) {}
+    /// Closes the eye on the left (`is_left`) or right side of the screen for a moment.
+    fn wink(&mut self, _is_left: bool) {}
+    /// Starts moving the mouth along the text of the balloon.
+    fn start_speech(&mut self) {}
+}
+
+#[cfg(feature = "alloc")]
+impl<A: FaceControl + ?Sized> FaceControl for alloc::boxed::Box<A> {
+    fn look_at(&mut self, horizontal: f32, vertical: f32) {
+        (**self).look_at(horizontal, vertical)
+    }
+    fn release_gaze(&mut self) {
+        (**self).release_gaze()
+    }
+    fn force_blink(&mut self) {
+        (**self).force_blink()
+    }
+    fn start_speech(&mut self) {
+        (**self).start_speech()
+    }
+    fn wink(&mut self, is_left: bool) {
+        (**self).wink(is_left)
+    }
 }
 
 pub struct AnimationRunner<Context, RootAnimator: Animator<Context>> {
     context: Context,
     counter: FrameCounter,
-    scheduled: FrameCounter,
+    scheduled: Option<FrameCounter>,
     animator: RootAnimator,
 }
 
@@ -43,37 +151,71 @@ impl<Context, RootAnimator: Animator<Context>> AnimationRunner<Context, RootAnim
     pub fn new(context: Context, frames_per_second: u64, animator: RootAnimator) -> Self {
         Self {
             context,
-            counter: FrameCounter { counter: 0, frames_per_second, },
-            scheduled: FrameCounter { counter: 0, frames_per_second, },
+            counter: FrameCounter::new(frames_per_second, Instant::default()),
+            scheduled: None,
             animator,
         }
     }
-    pub fn next(&mut self) {
-        if self.counter.is_after(&self.scheduled) {
-            self.scheduled = self.animator.next(self.counter, &mut self.context);
+    fn run_scheduled(&mut self) {
+        if self.counter.is_due(self.scheduled) {
+            self.scheduled = Some(self.animator.next(self.counter, &mut self.context));
         }
+    }
+    /// Runs the animator at the current frame and advances the counter by one frame period.
+    pub fn next(&mut self) {
+        self.run_scheduled();
         self.counter = self.counter.after_frames(1);
     }
+    /// Moves the counter to the absolute timestamp `now` and runs the animator if it is due.
+    pub fn next_at(&mut self, now: Instant) {
+        self.counter = self.counter.after_frames(1).at(now);
+        self.run_scheduled();
+    }
+    /// Moves the counter to `counter`, e.g. a frame of the schedule of the caller, and runs the animator if it is due.
+    ///
+    /// Animators which schedule themselves with `after_frames` from this counter come due exactly at the later frames
+    /// of the same schedule.
+    pub fn next_frame(&mut self, counter: FrameCounter) {
+        self.counter = counter;
+        self.run_scheduled();
+    }
+    pub fn counter(&self) -> FrameCounter {
+        self.counter
+    }
+    pub fn scheduled(&self) -> Option<FrameCounter> {
+        self.scheduled
+    }
     pub fn context(&mut self) -> &mut Context {
         &mut self.context
     }
+    pub fn context_ref(&self) -> &Context {
+        &self.context
+    }
+    pub fn animator(&mut self) -> &mut RootAnimator {
+        &mut self.animator
+    }
+    pub fn animator_ref(&self) -> &RootAnimator {
+        &self.animator
+    }
+    /// Runs the animator at the next call to `next`, `next_at` or `next_frame` even if it is not due yet.
+    pub fn reschedule(&mut self) {
+        self.scheduled = None;
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BreathAnimator {
-    c: u32,
+    origin: Option<Instant>,
 }
-impl Default for BreathAnimator {
-    fn default() -> Self {
-        Self {
-            c: 0,
-        }
-    }
+
+impl BreathAnimator {
+    const PERIOD_MILLISECONDS: u32 = 3300;
 }
 impl<'a, Context: MouthContext<'a>> Animator<Context> for BreathAnimator {
     fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
-        self.c = (self.c + 1) % 100;
-        let f = f32::sin((self.c as f32) * 2.0 * core::f32::consts::PI / 100.0);
+        let origin = *self.origin.get_or_insert(counter.time());
+        let phase = counter.time().milliseconds_since(&origin) % Self::PERIOD_MILLISECONDS;
+        let f = f32::sin((phase as f32) * 2.0 * core::f32::consts::PI / Self::PERIOD_MILLISECONDS as f32);
         context.set_breath(f);
         counter.after_milliseconds(33)
     }
@@ -83,90 +225,981 @@ fn rand_f32_range<Rng: RngCore>(rng: &mut Rng, from: f32, to: f32) -> f32 {
     (to - from) * (rng.next_u32() as f32) / (u32::MAX as f32) + from
 }
 fn rand_u32_nonuniform<Rng: RngCore>(rng: &mut Rng, from: u32, to: u32) -> u32 {
-    rng.next_u32() % (to - from + 1) + from
+    // The range is computed in u64 since it is 2^32 for the full range of u32.
+    (rng.next_u32() as u64 % (to as u64 - from as u64 + 1) + from as u64) as u32
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SaccadeAnimator {}
 
-impl Default for SaccadeAnimator {
-    fn default() -> Self {
-        Self {}
-    }
-}
-
-impl<Context: GazeContext + RandomGeneratorContext> Animator<Context> for SaccadeAnimator {
+impl<Context: GazeContext + RandomGeneratorContext + EventContext> Animator<Context> for SaccadeAnimator {
     fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
         let vertical = rand_f32_range(context.rng(), -1.0, 1.0);
         let horizontal = rand_f32_range(context.rng(), -1.0, 1.0);
         context.set_horizontal(horizontal);
         context.set_vertical(vertical);
+        context.push_event(AnimationEvent::Saccade { horizontal, vertical });
         counter.after_milliseconds(500 + 100 * rand_u32_nonuniform(context.rng(), 0, 20) as u64)
     }
 }
 
+/// Moves the gaze towards a target with a critically damped spring and holds it there until the target times out.
 #[derive(Debug)]
-pub struct BlinkAnimator {
-    is_open: bool,
+pub struct LookAtAnimator {
+    target: Option<(f32, f32)>,
+    hold_milliseconds: u32,
+    deadline: Option<Instant>,
+    last_time: Option<Instant>,
+    velocity: (f32, f32),
+}
+
+impl LookAtAnimator {
+    /// Angular frequency of the spring in radians per second.
+    const STIFFNESS: f32 = 12.0;
+    pub const DEFAULT_HOLD_MILLISECONDS: u32 = 2000;
+
+    pub fn new() -> Self {
+        Self {
+            target: None,
+            hold_milliseconds: Self::DEFAULT_HOLD_MILLISECONDS,
+            deadline: None,
+            last_time: None,
+            velocity: (0.0, 0.0),
+        }
+    }
+    /// Time a target is held after the last call to `look_at` before the gaze is released.
+    pub fn hold_milliseconds(&self) -> u32 {
+        self.hold_milliseconds
+    }
+    pub fn set_hold_milliseconds(&mut self, hold_milliseconds: u32) {
+        self.hold_milliseconds = hold_milliseconds;
+    }
+    /// Follows the normalized gaze position, -1.0..=1.0 on each axis. Calling it again moves the target and restarts
+    /// the hold time.
+    pub fn look_at(&mut self, horizontal: f32, vertical: f32) {
+        self.target = Some((horizontal.clamp(-1.0, 1.0), vertical.clamp(-1.0, 1.0)));
+        self.deadline = None;
+    }
+    pub fn release(&mut self) {
+        self.target = None;
+        self.velocity = (0.0, 0.0);
+    }
+    /// Returns true while a target is held.
+    pub fn is_active(&self) -> bool {
+        self.target.is_some()
+    }
+}
+
+impl Default for LookAtAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Default for BlinkAnimator {
+impl<Context: GazeContext> Animator<Context> for LookAtAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        let now = counter.time();
+        let elapsed = self.last_time.map_or(0, |last| now.milliseconds_since(&last).min(33));
+        self.last_time = Some(now);
+        let Some((horizontal, vertical)) = self.target else {
+            return counter.after_milliseconds(100);
+        };
+        let deadline = *self.deadline.get_or_insert(now.after_milliseconds(self.hold_milliseconds as u64));
+        if now.is_after(&deadline) {
+            self.release();
+            return counter.after_milliseconds(100);
+        }
+        let dt = elapsed as f32 / 1000.0;
+        let omega = Self::STIFFNESS;
+        let step = |position: f32, velocity: f32, target: f32| {
+            let acceleration = omega * omega * (target - position) - 2.0 * omega * velocity;
+            let velocity = velocity + acceleration * dt;
+            (position + velocity * dt, velocity)
+        };
+        let (x, vx) = step(context.horizontal(), self.velocity.0, horizontal);
+        let (y, vy) = step(context.vertical(), self.velocity.1, vertical);
+        let is_settled = (horizontal - x).abs() < 0.01 && (vertical - y).abs() < 0.01 && vx.abs() < 0.01 && vy.abs() < 0.01;
+        if is_settled {
+            context.set_horizontal(horizontal);
+            context.set_vertical(vertical);
+            self.velocity = (0.0, 0.0);
+            // Nothing moves until the target changes or times out.
+            return counter.at(deadline);
+        }
+        context.set_horizontal(x);
+        context.set_vertical(y);
+        self.velocity = (vx, vy);
+        counter.after_milliseconds(33)
+    }
+}
+
+/// Distribution of a random duration in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DurationDistribution {
+    Fixed(u32),
+    /// Uniformly distributed between `min` and `max`, inclusive.
+    Uniform { min: u32, max: u32 },
+    /// `min` plus an exponentially distributed time with mean `mean`, capped at `max`.
+    Exponential { min: u32, mean: u32, max: u32 },
+}
+
+impl DurationDistribution {
+    pub fn sample<Rng: RngCore>(&self, rng: &mut Rng) -> u32 {
+        match *self {
+            Self::Fixed(milliseconds) => milliseconds,
+            Self::Uniform { min, max } if max <= min => min,
+            Self::Uniform { min, max } => rand_u32_nonuniform(rng, min, max),
+            Self::Exponential { min, mean, max } => {
+                let u = rand_f32_range(rng, 0.0, 1.0).min(0.999_999);
+                let milliseconds = min as f32 - mean as f32 * (1.0 - u).ln();
+                (milliseconds as u32).clamp(min, max.max(min))
+            },
+        }
+    }
+}
+
+/// Timing of the blinks for one expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlinkProfile {
+    /// Time the eyes stay open between blinks.
+    pub interval: DurationDistribution,
+    /// Time the eyes stay fully closed.
+    pub closed: DurationDistribution,
+    pub close_milliseconds: u32,
+    pub open_milliseconds: u32,
+    /// Probability that a blink is followed by a second one right after the eyes have opened.
+    pub double_blink_probability: f32,
+    /// Time the eyes stay open between the two blinks of a double blink.
+    pub double_blink_interval: DurationDistribution,
+}
+
+impl BlinkProfile {
+    pub const NORMAL: Self = Self {
+        interval: DurationDistribution::Uniform { min: 2500, max: 4500 },
+        closed: DurationDistribution::Uniform { min: 300, max: 500 },
+        close_milliseconds: 100,
+        open_milliseconds: 150,
+        double_blink_probability: 0.1,
+        double_blink_interval: DurationDistribution::Uniform { min: 80, max: 150 },
+    };
+    pub const SLOW: Self = Self {
+        interval: DurationDistribution::Uniform { min: 4000, max: 7000 },
+        closed: DurationDistribution::Uniform { min: 600, max: 1000 },
+        close_milliseconds: 300,
+        open_milliseconds: 500,
+        double_blink_probability: 0.0,
+        ..Self::NORMAL
+    };
+    pub const RAPID: Self = Self {
+        interval: DurationDistribution::Exponential { min: 500, mean: 800, max: 3000 },
+        closed: DurationDistribution::Uniform { min: 80, max: 150 },
+        close_milliseconds: 60,
+        open_milliseconds: 100,
+        double_blink_probability: 0.3,
+        ..Self::NORMAL
+    };
+}
+
+impl Default for BlinkProfile {
     fn default() -> Self {
+        Self::NORMAL
+    }
+}
+
+/// Blink timing with optional overrides per expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlinkConfig {
+    pub default: BlinkProfile,
+    overrides: [Option<BlinkProfile>; Expression::VARIANT_COUNT],
+}
+
+impl BlinkConfig {
+    /// Blinks with `default` for every expression.
+    pub const fn new(default: BlinkProfile) -> Self {
+        Self {
+            default,
+            overrides: [None; Expression::VARIANT_COUNT],
+        }
+    }
+    /// Returns the config with `profile` used while the expression is `expression`. `None` falls back to the default.
+    pub const fn with_override(mut self, expression: Expression, profile: Option<BlinkProfile>) -> Self {
+        // `Into` is not usable in a const fn.
+        self.overrides[expression as usize] = profile;
+        self
+    }
+    pub fn set_override(&mut self, expression: Expression, profile: Option<BlinkProfile>) {
+        self.overrides[usize::from(expression)] = profile;
+    }
+    pub fn profile(&self, expression: Expression) -> &BlinkProfile {
+        self.overrides[usize::from(expression)].as_ref().unwrap_or(&self.default)
+    }
+}
+
+impl Default for BlinkConfig {
+    /// Blinks slowly while sleepy and rapidly while in doubt.
+    fn default() -> Self {
+        Self::new(BlinkProfile::NORMAL)
+            .with_override(Expression::Sleepy, Some(BlinkProfile::SLOW))
+            .with_override(Expression::Doubt, Some(BlinkProfile::RAPID))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum BlinkPhase {
+    Open,
+    Closing,
+    Closed,
+    #[default]
+    Opening,
+}
+
+/// Closes the eyes over a few frames, keeps them closed for a while and opens them again.
+#[derive(Debug, Default)]
+pub struct BlinkAnimator {
+    config: BlinkConfig,
+    phase: BlinkPhase,
+    origin: Option<Instant>,
+    is_double_blink: bool,
+}
+
+impl BlinkAnimator {
+    pub fn new(config: BlinkConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+    pub fn config(&self) -> &BlinkConfig {
+        &self.config
+    }
+    pub fn set_config(&mut self, config: BlinkConfig) {
+        self.config = config;
+    }
+    /// Returns false while the eyes are open and waiting for the next blink. Running the animator early in this state
+    /// starts a blink right away.
+    pub fn is_blinking(&self) -> bool {
+        self.phase != BlinkPhase::Open
+    }
+    /// Returns true between `BlinkStarted` and `BlinkEnded`.
+    pub fn is_mid_blink(&self) -> bool {
+        self.phase != BlinkPhase::Open && self.origin.is_some()
+    }
+    /// Opens the eyes at the next run and waits for the next blink from there.
+    pub fn reset(&mut self) {
+        self.phase = BlinkPhase::Opening;
+        self.origin = None;
+        self.is_double_blink = false;
+    }
+}
+
+impl<'a, Context: EyeContext<'a> + RandomGeneratorContext + EventContext> Animator<Context> for BlinkAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        let now = counter.time();
+        let profile = *self.config.profile(context.expression());
+        // Not started yet: finish opening immediately.
+        let elapsed = self.origin.map_or(u32::MAX, |origin| now.milliseconds_since(&origin));
+        match self.phase {
+            BlinkPhase::Open => {
+                self.phase = BlinkPhase::Closing;
+                self.origin = Some(now);
+                context.push_event(AnimationEvent::BlinkStarted);
+                counter.after_frames(1)
+            },
+            BlinkPhase::Closing if elapsed < profile.close_milliseconds => {
+                let t = elapsed as f32 / profile.close_milliseconds as f32;
+                EyeContext::set_open_ratio(context, 1.0 - Easing::EaseIn.apply(t));
+                counter.after_frames(1)
+            },
+            BlinkPhase::Closing => {
+                EyeContext::set_open_ratio(context, 0.0);
+                self.phase = BlinkPhase::Closed;
+                counter.after_milliseconds(profile.closed.sample(context.rng()) as u64)
+            },
+            BlinkPhase::Closed => {
+                self.phase = BlinkPhase::Opening;
+                self.origin = Some(now);
+                counter.after_frames(1)
+            },
+            BlinkPhase::Opening if elapsed < profile.open_milliseconds => {
+                let t = elapsed as f32 / profile.open_milliseconds as f32;
+                EyeContext::set_open_ratio(context, Easing::EaseOut.apply(t));
+                counter.after_frames(1)
+            },
+            BlinkPhase::Opening => {
+                EyeContext::set_open_ratio(context, 1.0);
+                self.phase = BlinkPhase::Open;
+                if self.origin.is_some() {
+                    context.push_event(AnimationEvent::BlinkEnded);
+                }
+                // The second blink of a double blink is never doubled again.
+                self.is_double_blink = !self.is_double_blink && self.origin.is_some()
+                    && rand_f32_range(context.rng(), 0.0, 1.0) < profile.double_blink_probability;
+                let interval = if self.is_double_blink { profile.double_blink_interval } else { profile.interval };
+                counter.after_milliseconds(interval.sample(context.rng()) as u64)
+            },
+        }
+    }
+    fn reset(&mut self) {
+        BlinkAnimator::reset(self)
+    }
+}
+
+/// Closes one eye for a moment after `wink` is called. The other eye keeps blinking as usual.
+#[derive(Debug, Default)]
+pub struct WinkAnimator {
+    is_left: bool,
+    origin: Option<Instant>,
+    is_winking: bool,
+}
+
+impl WinkAnimator {
+    pub const CLOSE_MILLISECONDS: u32 = 80;
+    pub const CLOSED_MILLISECONDS: u32 = 250;
+    pub const OPEN_MILLISECONDS: u32 = 120;
+    /// Interval at which the animator is polled while it does not wink.
+    const IDLE_MILLISECONDS: u64 = 1000;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn is_winking(&self) -> bool {
+        self.is_winking
+    }
+    /// Winks with the eye on the left (`is_left`) or right side of the screen from the next run on.
+    pub fn wink(&mut self, is_left: bool) {
+        self.is_left = is_left;
+        self.origin = None;
+        self.is_winking = true;
+    }
+    /// Stops a wink in progress. The eye is not opened again.
+    pub fn reset(&mut self) {
+        self.origin = None;
+        self.is_winking = false;
+    }
+}
+
+impl<'a, Context: EyeContext<'a>> Animator<Context> for WinkAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if !self.is_winking {
+            return counter.after_milliseconds(Self::IDLE_MILLISECONDS);
+        }
+        let origin = *self.origin.get_or_insert(counter.time());
+        let elapsed = counter.time().milliseconds_since(&origin);
+        let opening = Self::CLOSE_MILLISECONDS + Self::CLOSED_MILLISECONDS;
+        let (open_ratio, next) = if elapsed < Self::CLOSE_MILLISECONDS {
+            (1.0 - Easing::EaseIn.apply(elapsed as f32 / Self::CLOSE_MILLISECONDS as f32), counter.after_frames(1))
+        } else if elapsed < opening {
+            (0.0, counter.after_milliseconds((opening - elapsed) as u64))
+        } else if elapsed < opening + Self::OPEN_MILLISECONDS {
+            (Easing::EaseOut.apply((elapsed - opening) as f32 / Self::OPEN_MILLISECONDS as f32), counter.after_frames(1))
+        } else {
+            self.is_winking = false;
+            (1.0, counter.after_milliseconds(Self::IDLE_MILLISECONDS))
+        };
+        let state = context.eye_state(self.is_left);
+        context.set_eye_state(self.is_left, EyeState { open_ratio, ..state });
+        next
+    }
+    fn is_finished(&self) -> bool {
+        !self.is_winking
+    }
+    fn reset(&mut self) {
+        WinkAnimator::reset(self)
+    }
+}
+
+/// Blends a shape of the context from the shape on the screen to the shape of a new key whenever the key changes,
+/// e.g. the expression or the viseme.
+///
+/// Between transitions the animator only polls every `IDLE_MILLISECONDS`, so whoever changes the key should run it
+/// again right away, as [`FaceAnimator`] does.
+#[derive(Debug)]
+pub struct TransitionAnimator<S: Lerp, Key> {
+    duration_milliseconds: u32,
+    easing: Easing,
+    key: Option<Key>,
+    from: S,
+    origin: Option<Instant>,
+}
+
+/// Progress of a [`TransitionAnimator`] at one run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TransitionStep<S> {
+    Blending(S),
+    /// The transition has just ended.
+    Finished,
+    Idle,
+}
+
+impl<S: Lerp + Copy, Key: Copy + PartialEq> TransitionAnimator<S, Key> {
+    pub const IDLE_MILLISECONDS: u64 = 1000;
+
+    /// Creates an animator which blends over `duration_milliseconds` and starts from `from` once the key changes for
+    /// the first time.
+    pub fn with_duration(duration_milliseconds: u32, from: S) -> Self {
         Self {
-            is_open: false,
+            duration_milliseconds,
+            easing: Easing::EaseInOut,
+            key: None,
+            from,
+            origin: None,
+        }
+    }
+    pub fn duration_milliseconds(&self) -> u32 {
+        self.duration_milliseconds
+    }
+    /// Sets the length of the transitions. Zero switches the shape instantly.
+    pub fn set_duration_milliseconds(&mut self, duration_milliseconds: u32) {
+        self.duration_milliseconds = duration_milliseconds;
+    }
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+    /// Key seen at the last run, or `None` before the first one.
+    pub fn key(&self) -> Option<Key> {
+        self.key
+    }
+    /// Advances the transition to `now`. `current` is the shape on the screen and `to` the shape of `key`.
+    pub(crate) fn step(&mut self, now: Instant, key: Key, current: S, to: &S) -> TransitionStep<S> {
+        if self.key.is_some_and(|previous| previous != key) {
+            // Start from the shape on the screen so that a transition can be interrupted by another one.
+            self.from = current;
+            self.origin = Some(now);
+        }
+        self.key = Some(key);
+        let elapsed = self.origin.map_or(u32::MAX, |origin| now.milliseconds_since(&origin));
+        if elapsed < self.duration_milliseconds {
+            let t = self.easing.apply(elapsed as f32 / self.duration_milliseconds as f32);
+            TransitionStep::Blending(self.from.lerp(to, t))
+        } else if self.origin.take().is_some() {
+            TransitionStep::Finished
+        } else {
+            TransitionStep::Idle
+        }
+    }
+}
+
+/// Blends the expression shape whenever the expression changes.
+pub type ExpressionTransitionAnimator = TransitionAnimator<ExpressionShape, Expression>;
+
+impl ExpressionTransitionAnimator {
+    pub const DEFAULT_DURATION_MILLISECONDS: u32 = 300;
+
+    pub fn new() -> Self {
+        Self::with_duration(Self::DEFAULT_DURATION_MILLISECONDS, ExpressionShape::NEUTRAL)
+    }
+}
+
+impl Default for ExpressionTransitionAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Context: ExpressionContext + EventContext> Animator<Context> for ExpressionTransitionAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        let expression = context.expression();
+        let to = ExpressionShape::of(expression);
+        let step = self.step(counter.time(), expression, context.expression_shape(), &to);
+        if let TransitionStep::Blending(shape) = step {
+            context.set_expression_shape(Some(shape));
+            return counter.after_frames(1);
+        }
+        if step == TransitionStep::Finished {
+            context.push_event(AnimationEvent::ExpressionTransitionFinished(expression));
+        }
+        // Keep the shape pinned so that a new expression is not drawn before its transition starts.
+        context.set_expression_shape(Some(to));
+        counter.after_milliseconds(Self::IDLE_MILLISECONDS)
+    }
+}
+
+/// Blends the mouth shape whenever the viseme changes.
+pub type VisemeTransitionAnimator = TransitionAnimator<MouthShape, Option<Viseme>>;
+
+impl VisemeTransitionAnimator {
+    pub const DEFAULT_DURATION_MILLISECONDS: u32 = 80;
+
+    pub fn new() -> Self {
+        Self::with_duration(Self::DEFAULT_DURATION_MILLISECONDS, MouthShape::CLOSED)
+    }
+}
+
+impl Default for VisemeTransitionAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, Context: MouthContext<'a>> Animator<Context> for VisemeTransitionAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        let viseme = context.viseme();
+        let open_ratio_shape = MouthShape::from_open_ratio(context.open_ratio());
+        let to = viseme.map_or(open_ratio_shape, MouthShape::of);
+        let current = context.mouth_shape().unwrap_or(open_ratio_shape);
+        if let TransitionStep::Blending(shape) = self.step(counter.time(), viseme, current, &to) {
+            context.set_mouth_shape(Some(shape));
+            return counter.after_frames(1);
         }
+        // Without a viseme the open ratio drives the mouth directly, e.g. from lip-sync.
+        context.set_mouth_shape(viseme.map(MouthShape::of));
+        counter.after_milliseconds(Self::IDLE_MILLISECONDS)
     }
 }
 
-impl<'a, Context: EyeContext<'a> + RandomGeneratorContext> Animator<Context> for BlinkAnimator {
+/// Tilts the head while the expression is `Doubt` and straightens it otherwise.
+#[derive(Debug, Default)]
+pub struct HeadTiltAnimator {
+    last_time: Option<Instant>,
+}
+
+impl HeadTiltAnimator {
+    const TILT_ANGLE: f32 = 0.2;
+    /// Radians per second.
+    const ANGULAR_SPEED: f32 = 0.6;
+}
+
+impl<'a, Context: MouthContext<'a>> Animator<Context> for HeadTiltAnimator {
     fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
-        self.is_open = !self.is_open;
-        if self.is_open {
-            // open
-            EyeContext::set_open_ratio(context, 1.0);
-            counter.after_milliseconds(2500 + 100 * rand_u32_nonuniform(context.rng(), 0, 20) as u64)
+        let now = counter.time();
+        // Do not count the time spent idle as movement.
+        let elapsed = self.last_time.map_or(0, |last| now.milliseconds_since(&last).min(33));
+        self.last_time = Some(now);
+        let target = if context.expression() == Expression::Doubt { Self::TILT_ANGLE } else { 0.0 };
+        let step = Self::ANGULAR_SPEED * elapsed as f32 / 1000.0;
+        let mut head = context.head();
+        head.angle = if (target - head.angle).abs() <= step {
+            target
+        } else if target > head.angle {
+            head.angle + step
         } else {
-            // close
-            EyeContext::set_open_ratio(context, 0.0);
-            counter.after_milliseconds(300 + 10 * rand_u32_nonuniform(context.rng(), 0, 20) as u64)
+            head.angle - step
+        };
+        context.set_head(head);
+        counter.after_milliseconds(if head.angle == target { 100 } else { 33 })
+    }
+}
+
+/// Bobs the head up and down while the mouth is moving.
+#[derive(Debug, Default)]
+pub struct HeadBobAnimator {
+    origin: Option<Instant>,
+    last_talking: Option<Instant>,
+    last_open_ratio: f32,
+}
+
+impl HeadBobAnimator {
+    const PERIOD_MILLISECONDS: u32 = 800;
+    const AMPLITUDE: f32 = 3.0;
+    /// Keep bobbing through short pauses between words.
+    const HOLD_MILLISECONDS: u32 = 500;
+}
+
+impl<'a, Context: MouthContext<'a>> Animator<Context> for HeadBobAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        let now = counter.time();
+        let open_ratio = context.open_ratio();
+        if (open_ratio - self.last_open_ratio).abs() > 0.05 {
+            self.last_talking = Some(now);
         }
+        self.last_open_ratio = open_ratio;
+        let is_talking = self.last_talking.is_some_and(|last| now.milliseconds_since(&last) < Self::HOLD_MILLISECONDS);
+        let mut head = context.head();
+        if is_talking {
+            let origin = *self.origin.get_or_insert(now);
+            let phase = now.milliseconds_since(&origin) % Self::PERIOD_MILLISECONDS;
+            head.offset_y = Self::AMPLITUDE * f32::sin((phase as f32) * 2.0 * core::f32::consts::PI / Self::PERIOD_MILLISECONDS as f32);
+        } else {
+            // Settle back to the rest position and start the next bob from there.
+            self.origin = None;
+            head.offset_y = if head.offset_y.abs() <= 1.0 { 0.0 } else { head.offset_y - head.offset_y.signum() };
+        }
+        context.set_head(head);
+        counter.after_milliseconds(if is_talking || head.offset_y != 0.0 { 33 } else { 100 })
     }
 }
 
+/// Built-in behaviors of the [`FaceAnimator`].
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive, VariantCount)]
+pub enum Behavior {
+    Breath,
+    Saccade,
+    LookAt,
+    Blink,
+    ExpressionTransition,
+    HeadTilt,
+    HeadBob,
+    VisemeTransition,
+    Speech,
+    Wink,
+}
+
+impl Behavior {
+    pub const ALL: [Self; Self::VARIANT_COUNT] = [Self::Breath, Self::Saccade, Self::LookAt, Self::Blink, Self::ExpressionTransition, Self::HeadTilt, Self::HeadBob, Self::VisemeTransition, Self::Speech, Self::Wink];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BehaviorState {
+    #[default]
+    Enabled,
+    /// Stops the behavior and keeps the parameters it drives as they are, e.g. to freeze the gaze.
+    Paused,
+    /// Stops the behavior and moves the parameters it drives back to their rest values once.
+    Disabled,
+}
+
 #[derive(Debug)]
 pub struct FaceAnimator {
+    states: [BehaviorState; Behavior::VARIANT_COUNT],
+    pending_rests: [bool; Behavior::VARIANT_COUNT],
+    /// Next run of each behavior. `None` runs it at the next frame.
+    counters: [Option<FrameCounter>; Behavior::VARIANT_COUNT],
     breath: BreathAnimator,
     saccade: SaccadeAnimator,
+    look_at: LookAtAnimator,
     blink: BlinkAnimator,
-    breath_counter: FrameCounter,
-    saccade_counter: FrameCounter,
-    blink_counter: FrameCounter,
+    expression_transition: ExpressionTransitionAnimator,
+    head_tilt: HeadTiltAnimator,
+    head_bob: HeadBobAnimator,
+    viseme_transition: VisemeTransitionAnimator,
+    speech: SpeechAnimator,
+    wink: WinkAnimator,
 }
 
 impl FaceAnimator {
     pub fn new() -> Self {
         Self {
+            states: [BehaviorState::Enabled; Behavior::VARIANT_COUNT],
+            pending_rests: [false; Behavior::VARIANT_COUNT],
+            counters: [None; Behavior::VARIANT_COUNT],
             breath: BreathAnimator::default(),
             saccade: SaccadeAnimator::default(),
+            look_at: LookAtAnimator::default(),
             blink: BlinkAnimator::default(),
-            breath_counter: FrameCounter::default(),
-            saccade_counter: FrameCounter::default(),
-            blink_counter: FrameCounter::default(),
+            expression_transition: ExpressionTransitionAnimator::default(),
+            head_tilt: HeadTiltAnimator::default(),
+            head_bob: HeadBobAnimator::default(),
+            viseme_transition: VisemeTransitionAnimator::default(),
+            speech: SpeechAnimator::default(),
+            wink: WinkAnimator::default(),
+        }
+    }
+    pub fn behavior(&self, behavior: Behavior) -> BehaviorState {
+        self.states[behavior as usize]
+    }
+    pub fn set_behavior(&mut self, behavior: Behavior, state: BehaviorState) {
+        self.states[behavior as usize] = state;
+        self.pending_rests[behavior as usize] = state == BehaviorState::Disabled;
+        if behavior == Behavior::LookAt && state == BehaviorState::Disabled {
+            self.look_at.release();
         }
     }
+    fn is_enabled(&self, behavior: Behavior) -> bool {
+        self.states[behavior as usize] == BehaviorState::Enabled
+    }
+    /// Runs `behavior` at the next frame.
+    fn reschedule(&mut self, behavior: Behavior) {
+        self.counters[behavior as usize] = None;
+    }
+    /// Animator which drives `behavior`.
+    fn animator<'a, Context: FaceContext<'a> + BalloonContext<'a> + RandomGeneratorContext + EventContext>(&mut self, behavior: Behavior) -> &mut dyn Animator<Context> {
+        match behavior {
+            Behavior::Breath => &mut self.breath,
+            Behavior::Saccade => &mut self.saccade,
+            Behavior::LookAt => &mut self.look_at,
+            Behavior::Blink => &mut self.blink,
+            Behavior::ExpressionTransition => &mut self.expression_transition,
+            Behavior::HeadTilt => &mut self.head_tilt,
+            Behavior::HeadBob => &mut self.head_bob,
+            Behavior::VisemeTransition => &mut self.viseme_transition,
+            Behavior::Speech => &mut self.speech,
+            Behavior::Wink => &mut self.wink,
+        }
+    }
+    /// Moves the parameters of the behaviors which have just been disabled back to their rest values.
+    fn rest<'a, Context: FaceContext<'a> + EventContext>(&mut self, context: &mut Context) {
+        for behavior in Behavior::ALL {
+            if !core::mem::take(&mut self.pending_rests[behavior as usize]) {
+                continue;
+            }
+            let mut head = context.head();
+            match behavior {
+                Behavior::Breath => context.set_breath(0.0),
+                Behavior::Saccade | Behavior::LookAt => {
+                    context.set_horizontal(0.0);
+                    context.set_vertical(0.0);
+                },
+                Behavior::Blink => {
+                    // Close the pair of events of a blink which is cut short.
+                    if self.blink.is_mid_blink() {
+                        context.push_event(AnimationEvent::BlinkEnded);
+                    }
+                    self.blink.reset();
+                    EyeContext::set_open_ratio(context, 1.0);
+                },
+                Behavior::ExpressionTransition => context.set_expression_shape(None),
+                Behavior::HeadTilt => {
+                    head.angle = 0.0;
+                    context.set_head(head);
+                },
+                Behavior::HeadBob => {
+                    head.offset_y = 0.0;
+                    context.set_head(head);
+                },
+                Behavior::VisemeTransition => context.set_mouth_shape(None),
+                Behavior::Speech => {
+                    if self.speech.is_speaking() {
+                        context.push_event(AnimationEvent::SpeechFinished);
+                    }
+                    self.speech.stop();
+                    context.set_viseme(None);
+                    MouthContext::set_open_ratio(context, 0.0);
+                },
+                Behavior::Wink => {
+                    self.wink.reset();
+                    for is_left in [true, false] {
+                        let state = context.eye_state(is_left);
+                        context.set_eye_state(is_left, EyeState { open_ratio: 1.0, ..state });
+                    }
+                },
+            }
+        }
+    }
+    pub fn blink_config(&self) -> &BlinkConfig {
+        self.blink.config()
+    }
+    pub fn set_blink_config(&mut self, config: BlinkConfig) {
+        self.blink.set_config(config);
+    }
+    pub fn look_at_animator(&mut self) -> &mut LookAtAnimator {
+        &mut self.look_at
+    }
+    /// Makes the gaze follow the normalized position `horizontal`, `vertical` from the next run on.
+    pub fn look_at(&mut self, horizontal: f32, vertical: f32) {
+        self.look_at.look_at(horizontal, vertical);
+        self.reschedule(Behavior::LookAt);
+    }
+    /// Stops following the gaze target and resumes the random saccades.
+    pub fn release_gaze(&mut self) {
+        self.look_at.release();
+        self.reschedule(Behavior::LookAt);
+    }
+    pub fn expression_transition_animator(&mut self) -> &mut ExpressionTransitionAnimator {
+        &mut self.expression_transition
+    }
+    pub fn viseme_transition_animator(&mut self) -> &mut VisemeTransitionAnimator {
+        &mut self.viseme_transition
+    }
+    /// Winks with the eye on the left (`is_left`) or right side of the screen from the next run on.
+    pub fn wink(&mut self, is_left: bool) {
+        self.wink.wink(is_left);
+        self.reschedule(Behavior::Wink);
+    }
+    pub fn speech_config(&self) -> &SpeechConfig {
+        self.speech.config()
+    }
+    pub fn set_speech_config(&mut self, config: SpeechConfig) {
+        self.speech.set_config(config);
+    }
+    pub fn is_speaking(&self) -> bool {
+        self.speech.is_speaking()
+    }
+    /// Starts speaking the text of the balloon from the next run on.
+    pub fn start_speech(&mut self) {
+        self.speech.start();
+        self.reschedule(Behavior::Speech);
+    }
+    /// Makes the blink animator start a blink the next time this animator runs, unless a blink is in progress.
+    pub fn force_blink(&mut self) {
+        if !self.blink.is_blinking() {
+            self.reschedule(Behavior::Blink);
+        }
+    }
+}
+
+impl Default for FaceAnimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaceControl for BreathAnimator {}
+impl FaceControl for SaccadeAnimator {}
+impl FaceControl for BlinkAnimator {}
+impl<S: Lerp, Key> FaceControl for TransitionAnimator<S, Key> {}
+impl FaceControl for HeadTiltAnimator {}
+impl FaceControl for HeadBobAnimator {}
+
+impl FaceControl for WinkAnimator {
+    fn wink(&mut self, is_left: bool) {
+        WinkAnimator::wink(self, is_left)
+    }
+}
+
+impl FaceControl for LookAtAnimator {
+    fn look_at(&mut self, horizontal: f32, vertical: f32) {
+        LookAtAnimator::look_at(self, horizontal, vertical)
+    }
+    fn release_gaze(&mut self) {
+        self.release()
+    }
+}
+
+impl FaceControl for FaceAnimator {
+    fn look_at(&mut self, horizontal: f32, vertical: f32) {
+        FaceAnimator::look_at(self, horizontal, vertical)
+    }
+    fn release_gaze(&mut self) {
+        FaceAnimator::release_gaze(self)
+    }
+    fn force_blink(&mut self) {
+        FaceAnimator::force_blink(self)
+    }
+    fn start_speech(&mut self) {
+        FaceAnimator::start_speech(self)
+    }
+    fn wink(&mut self, is_left: bool) {
+        FaceAnimator::wink(self, is_left)
+    }
 }
 
-impl<'a, Context: FaceContext<'a> + RandomGeneratorContext> Animator<Context> for FaceAnimator {
+impl<'a, Context: FaceContext<'a> + BalloonContext<'a> + RandomGeneratorContext + EventContext> Animator<Context> for FaceAnimator {
     fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
-        if counter.is_after(&self.breath_counter) {
-            self.breath_counter = self.breath.next(counter, context);
+        self.rest(context);
+        // The transitions idle until their key changes.
+        if self.expression_transition.key().is_some_and(|expression| expression != context.expression()) {
+            self.reschedule(Behavior::ExpressionTransition);
+        }
+        if self.viseme_transition.key().is_some_and(|viseme| viseme != context.viseme()) {
+            self.reschedule(Behavior::VisemeTransition);
         }
-        if counter.is_after(&self.saccade_counter) {
-            self.saccade_counter = self.saccade.next(counter, context);
+        for behavior in Behavior::ALL {
+            // Random saccades are suppressed while the gaze follows a target.
+            let is_running = self.is_enabled(behavior) && !(behavior == Behavior::Saccade && self.look_at.is_active());
+            let scheduled = self.counters[behavior as usize];
+            // Behaviors which do not run are not scheduled, so they run as soon as they are enabled again.
+            self.counters[behavior as usize] = if !is_running {
+                None
+            } else if counter.is_due(scheduled) {
+                Some(self.animator(behavior).next(counter, context))
+            } else {
+                scheduled
+            };
+        }
+        // Poll slowly when every behavior is stopped.
+        counter.earliest(self.counters.into_iter().flatten()).unwrap_or_else(|| counter.after_milliseconds(1000))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_duration_over_the_full_range_does_not_overflow() {
+        use rand_core::SeedableRng;
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(1);
+        let distribution = DurationDistribution::Uniform { min: 0, max: u32::MAX };
+        for _ in 0..16 {
+            distribution.sample(&mut rng);
         }
-        if counter.is_after(&self.blink_counter) {
-            self.blink_counter = self.blink.next(counter, context);
+    }
+
+    #[test]
+    fn blink_overrides_apply_to_their_expression_only() {
+        let config = BlinkConfig::default();
+        assert_eq!(config.profile(Expression::Sleepy), &BlinkProfile::SLOW);
+        assert_eq!(config.profile(Expression::Doubt), &BlinkProfile::RAPID);
+        assert_eq!(config.profile(Expression::Neutral), &BlinkProfile::NORMAL);
+    }
+
+    #[cfg(feature = "alloc")]
+    fn face_runner() -> AnimationRunner<crate::components::face::DrawContext<embedded_graphics::pixelcolor::BinaryColor, alloc::string::String>, FaceAnimator> {
+        AnimationRunner::new(Default::default(), 30, FaceAnimator::new())
+    }
+
+    /// Drains the events of the runner and returns true if `event` was among them.
+    #[cfg(feature = "alloc")]
+    fn has_event(runner: &mut AnimationRunner<crate::components::face::DrawContext<embedded_graphics::pixelcolor::BinaryColor, alloc::string::String>, FaceAnimator>, event: AnimationEvent) -> bool {
+        let mut found = false;
+        while let Some(popped) = runner.context().events.pop() {
+            found |= popped == event;
         }
-        [self.breath_counter, self.saccade_counter, self.blink_counter].into_iter().min_by(|x, y| x.counter.cmp(&y.counter)).unwrap() 
+        found
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn disabling_blink_mid_blink_ends_the_blink() {
+        let mut runner = face_runner();
+        runner.animator().force_blink();
+        runner.next();
+        runner.animator().force_blink();
+        runner.next();
+        assert!(has_event(&mut runner, AnimationEvent::BlinkStarted));
+        runner.animator().set_behavior(Behavior::Blink, BehaviorState::Disabled);
+        runner.reschedule();
+        runner.next();
+        assert!(has_event(&mut runner, AnimationEvent::BlinkEnded));
+        assert_eq!(runner.context().eye_open_ratio, 1.0);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn disabling_speech_mid_utterance_finishes_the_speech() {
+        use crate::components::balloon::BalloonContext;
+        let mut runner = face_runner();
+        runner.context().set_text(Some("hello"));
+        runner.animator().start_speech();
+        runner.next();
+        assert!(runner.animator_ref().is_speaking());
+        runner.animator().set_behavior(Behavior::Speech, BehaviorState::Disabled);
+        runner.reschedule();
+        runner.next();
+        assert!(!runner.animator_ref().is_speaking());
+        assert!(has_event(&mut runner, AnimationEvent::SpeechFinished));
+    }
+
+    struct ExpressionOnly {
+        expression: Expression,
+        finished: u32,
+    }
+
+    impl ExpressionContext for ExpressionOnly {
+        fn expression(&self) -> Expression {
+            self.expression
+        }
+    }
+
+    impl EventContext for ExpressionOnly {
+        fn push_event(&mut self, event: AnimationEvent) {
+            if let AnimationEvent::ExpressionTransitionFinished(_) = event {
+                self.finished += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn expression_transition_idles_between_transitions() {
+        let mut animator = ExpressionTransitionAnimator::new();
+        let mut context = ExpressionOnly { expression: Expression::Neutral, finished: 0 };
+        let counter = FrameCounter::new(30, Instant::from_millis(0));
+        let idle = animator.next(counter, &mut context);
+        assert_eq!(idle.time(), Instant::from_millis(ExpressionTransitionAnimator::IDLE_MILLISECONDS));
+
+        context.expression = Expression::Happy;
+        let mut frame = counter.after_frames(1);
+        let mut scheduled = animator.next(frame, &mut context);
+        assert_eq!(scheduled.time(), counter.after_frames(2).time());
+        while context.finished == 0 && frame.time().milliseconds_since(&counter.time()) < 1000 {
+            frame = scheduled;
+            scheduled = animator.next(frame, &mut context);
+        }
+        assert_eq!(context.finished, 1);
+        assert_eq!(scheduled.time(), frame.time().after_milliseconds(ExpressionTransitionAnimator::IDLE_MILLISECONDS));
+    }
+}
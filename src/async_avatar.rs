@@ -0,0 +1,199 @@
+use core::future::{Future, poll_fn};
+use core::pin::pin;
+use core::str::FromStr;
+use core::task::Poll;
+
+use embedded_graphics::prelude::{DrawTarget, PixelColor};
+
+use crate::{Animator, Avatar, Command, FaceControl, Instant, Timer};
+use crate::components::face::DrawContext;
+
+/// Timer which can wait for a deadline without blocking the executor.
+pub trait AsyncTimer: Timer {
+    fn wait_until(&mut self, deadline: Instant) -> impl Future<Output = ()>;
+}
+
+/// Channel-like source of [`Command`]s. Receiving must be cancel-safe, since it is dropped whenever a frame is due.
+pub trait CommandSource<String> {
+    fn receive(&mut self) -> impl Future<Output = Command<String>>;
+}
+
+enum Either<A, B> {
+    First(A),
+    Second(B),
+}
+
+async fn select<A: Future, B: Future>(a: A, b: B) -> Either<A::Output, B::Output> {
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::First(output));
+        }
+        if let Poll::Ready(output) = b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Second(output));
+        }
+        Poll::Pending
+    }).await
+}
+
+impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr + Clone, RootAnimator: Animator<DrawContext<Color, String>> + FaceControl> Avatar<'a, Color, String, RootAnimator> {
+    /// Waits until the next frame is due or a command arrives, and applies the received command.
+    ///
+    /// Returns `false` when `Command::Stop` has been received.
+    pub async fn wait_for_frame<T: AsyncTimer, S: CommandSource<String>>(&mut self, timer: &mut T, commands: &mut S) -> bool {
+        let Some(deadline) = self.next_frame_time() else {
+            return true;
+        };
+        match select(commands.receive(), timer.wait_until(deadline)).await {
+            Either::First(Command::Stop) => false,
+            Either::First(command) => {
+                self.apply(command);
+                true
+            },
+            Either::Second(()) => true,
+        }
+    }
+    /// Renders frames until `Command::Stop` is received, sleeping between frames.
+    #[cfg(feature = "alloc")]
+    pub async fn run_async<D, T, S>(&mut self, draw_target: &mut D, timer: &mut T, commands: &mut S) -> Result<(), D::Error>
+        where
+            D: DrawTarget<Color = Color>,
+            T: AsyncTimer,
+            S: CommandSource<String>,
+    {
+        loop {
+            self.run(draw_target, timer)?;
+            if !self.wait_for_frame(timer, commands).await {
+                return Ok(());
+            }
+        }
+    }
+    /// Same as `run_async`, but renders with a caller-provided scratch buffer.
+    pub async fn run_async_with_scratch<D, T, S>(&mut self, draw_target: &mut D, timer: &mut T, commands: &mut S, scratch: &mut [u8]) -> Result<(), D::Error>
+        where
+            D: DrawTarget<Color = Color>,
+            T: AsyncTimer,
+            S: CommandSource<String>,
+    {
+        loop {
+            self.run_with_scratch(draw_target, timer, scratch)?;
+            if !self.wait_for_frame(timer, commands).await {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::{collections::VecDeque, rc::Rc, string::{String, ToString}};
+    use core::{cell::Cell, future::Future, pin::pin, task::{Context, Poll, RawWaker, RawWakerVTable, Waker}};
+
+    use embedded_graphics::{pixelcolor::BinaryColor, prelude::{DrawTarget, OriginDimensions, Pixel, Size}};
+
+    use super::*;
+    use crate::{BasicPaletteKey, Expression, Palette};
+
+    /// Clock which jumps straight to the deadline instead of sleeping.
+    struct MockClock {
+        now: Rc<Cell<u64>>,
+    }
+
+    impl Timer for MockClock {
+        fn timestamp_milliseconds(&self) -> u64 {
+            self.now.get()
+        }
+    }
+
+    impl AsyncTimer for MockClock {
+        async fn wait_until(&mut self, deadline: Instant) {
+            let now = self.now();
+            if deadline.is_after(&now) {
+                self.now.set(self.now.get() + deadline.milliseconds_since(&now) as u64);
+            }
+        }
+    }
+
+    /// Commands which become available at the given timestamps of the mock clock.
+    struct ScriptedCommands {
+        now: Rc<Cell<u64>>,
+        script: VecDeque<(u64, Command<String>)>,
+    }
+
+    impl CommandSource<String> for ScriptedCommands {
+        fn receive(&mut self) -> impl Future<Output = Command<String>> {
+            poll_fn(|_| match self.script.front() {
+                Some((at, _)) if *at <= self.now.get() => Poll::Ready(self.script.pop_front().unwrap().1),
+                _ => Poll::Pending,
+            })
+        }
+    }
+
+    struct CountingDisplay {
+        pixels: usize,
+    }
+
+    impl OriginDimensions for CountingDisplay {
+        fn size(&self) -> Size {
+            Size::new(320, 240)
+        }
+    }
+
+    impl DrawTarget for CountingDisplay {
+        type Color = BinaryColor;
+        type Error = core::convert::Infallible;
+        fn draw_iter<I: IntoIterator<Item = Pixel<Self::Color>>>(&mut self, pixels: I) -> Result<(), Self::Error> {
+            self.pixels += pixels.into_iter().count();
+            Ok(())
+        }
+    }
+
+    /// Waker which does nothing, as `block_on` polls in a loop anyway.
+    fn noop_waker() -> Waker {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+        const RAW: RawWaker = RawWaker::new(core::ptr::null(), &VTABLE);
+        // SAFETY: the vtable functions never touch the data pointer.
+        unsafe { Waker::from_raw(RAW) }
+    }
+
+    /// Polls `future` until it completes, failing if it does not within `max_polls`.
+    fn block_on<F: Future>(future: F, max_polls: usize) -> F::Output {
+        let mut future = pin!(future);
+        let waker = noop_waker();
+        let mut context = Context::from_waker(&waker);
+        for _ in 0..max_polls {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+        panic!("future did not complete within {max_polls} polls");
+    }
+
+    #[test]
+    fn run_async_renders_frames_and_returns_on_stop() {
+        let now = Rc::new(Cell::new(0u64));
+        let mut context: DrawContext<BinaryColor, String> = DrawContext::default();
+        context.palette.set_color(&BasicPaletteKey::Primary, BinaryColor::On);
+        context.palette.set_color(&BasicPaletteKey::Background, BinaryColor::Off);
+        let mut avatar = Avatar::new(context, 30);
+        let mut timer = MockClock { now: now.clone() };
+        let mut commands = ScriptedCommands {
+            now: now.clone(),
+            script: VecDeque::from([
+                (500, Command::SetExpression(Expression::Happy)),
+                (1000, Command::Say("hello".to_string())),
+                (2000, Command::Stop),
+            ]),
+        };
+        let mut display = CountingDisplay { pixels: 0 };
+        block_on(avatar.run_async(&mut display, &mut timer, &mut commands), 10_000).unwrap();
+        assert!(commands.script.is_empty());
+        // The clock jumps from frame to frame, so the stop is seen at the first frame after it was sent.
+        assert!((2000..2034).contains(&now.get()), "stopped at {} ms", now.get());
+        // About 30 frames per second for two seconds.
+        assert!((55..=65).contains(&avatar.frame_number()), "{} frames", avatar.frame_number());
+        assert!(display.pixels > 0);
+        assert_eq!(avatar.context().expression, Expression::Happy);
+    }
+}
@@ -1,56 +1,643 @@
 use core::str::FromStr;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
-use embedded_graphics::{prelude::{PixelColor, DrawTarget, IntoStorage}, primitives::Rectangle, Drawable};
+use embedded_graphics::{prelude::{PixelColor, DrawTarget, Point}, draw_target::DrawTargetExt, primitives::Rectangle};
 
-use crate::{components::{face::{Face, DrawContext}, effect::Effect, balloon::Balloon}, animation::{AnimationRunner, FaceAnimator}, Component};
+use crate::{components::{face::{Face, DrawContext, DrawableFace}, eye::EyeStyle, eye_shape::EyeShape, effect::{Effect, DrawableEffect}, balloon::{Balloon, BalloonContext, BalloonSnapshot}}, animation::{Animator, AnimationRunner, FrameCounter, Behavior, BehaviorState, BlinkConfig, FaceAnimator, FaceControl}, Component, Instant, DamageList, draw_damaged, erase_uncovered, Palette, BasicPaletteKey, sprite::draw_with_scratch_oriented, REFERENCE_BOUNDING_RECT, AnimationEvent, EventContext, Expression, Layout, Orientation, OrientationTransform, SpeechConfig, Viseme};
 
 pub trait Timer {
     fn timestamp_milliseconds(&self) -> u64; 
+    fn now(&self) -> Instant {
+        Instant::from_millis(self.timestamp_milliseconds())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FramePolicy {
+    /// Render once and drop the missed frames, keeping the original frame schedule.
+    #[default]
+    Drop,
+    /// Step the animation at each missed frame time (at most `max_frames` steps) before rendering.
+    CatchUp { max_frames: u32 },
+    /// Render once and restart the frame schedule from now. The animation advances by one frame period
+    /// per rendered frame, so late frames stretch the animation time.
+    Stretch,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub rendered: bool,
+    pub frames_dropped: u32,
+    pub render_milliseconds: u64,
 }
 
-pub struct Avatar<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr> {
-    last_time: Option<u64>,
+pub struct Avatar<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr + Clone, RootAnimator: Animator<DrawContext<Color, String>> = FaceAnimator> {
+    /// Next frame of the schedule. `None` renders at the next call to `run`.
+    next_frame: Option<FrameCounter>,
     frames_per_second: u64,
+    frame_policy: FramePolicy,
+    viewport: Rectangle,
+    orientation: Orientation,
     face: Face<'a, DrawContext<Color, String>>,
     effect: Effect<'a, DrawContext<Color, String>>,
     balloon: Balloon<'a, DrawContext<Color, String>>,
-    runner: AnimationRunner<DrawContext<Color, String>, FaceAnimator>,
+    runner: AnimationRunner<DrawContext<Color, String>, RootAnimator>,
+    previous_face: Option<DrawableFace<Color>>,
+    previous_effect: Option<DrawableEffect<Color>>,
+    previous_balloon: Option<BalloonSnapshot<Color>>,
+    previous_balloon_text: Option<String>,
+    is_balloon_visible: bool,
+    frame_number: u64,
+    damage: DamageList,
+    #[cfg(feature = "alloc")]
+    scratch: Vec<u8>,
 }
 
-impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr> Avatar<'a, Color, String> {
+impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr + Clone> Avatar<'a, Color, String> {
     pub fn new(context: DrawContext<Color, String>, frames_per_second: u64) -> Self {
+        Self::with_animator(context, frames_per_second, FaceAnimator::new())
+    }
+    /// Time a gaze target is held after the last call to `look_at`.
+    pub fn set_gaze_hold_milliseconds(&mut self, hold_milliseconds: u32) {
+        self.runner.animator().look_at_animator().set_hold_milliseconds(hold_milliseconds);
+    }
+    /// Length of the blend between two expressions. Zero switches the shape instantly.
+    pub fn set_expression_transition_milliseconds(&mut self, duration_milliseconds: u32) {
+        self.runner.animator().expression_transition_animator().set_duration_milliseconds(duration_milliseconds);
+    }
+    /// Length of the blend between two visemes. Zero switches the mouth shape instantly.
+    pub fn set_viseme_transition_milliseconds(&mut self, duration_milliseconds: u32) {
+        self.runner.animator().viseme_transition_animator().set_duration_milliseconds(duration_milliseconds);
+    }
+    pub fn speech_config(&self) -> &SpeechConfig {
+        self.runner.animator_ref().speech_config()
+    }
+    /// Sets the speaking rate in characters per second and how long the balloon stays after the speech.
+    pub fn set_speech_config(&mut self, config: SpeechConfig) {
+        self.runner.animator().set_speech_config(config);
+    }
+    pub fn is_speaking(&self) -> bool {
+        self.runner.animator_ref().is_speaking()
+    }
+    pub fn blink_config(&self) -> &BlinkConfig {
+        self.runner.animator_ref().blink_config()
+    }
+    pub fn set_blink_config(&mut self, config: BlinkConfig) {
+        self.runner.animator().set_blink_config(config);
+    }
+    pub fn behavior(&self, behavior: Behavior) -> BehaviorState {
+        self.runner.animator_ref().behavior(behavior)
+    }
+    /// Enables, pauses or disables one of the built-in behaviors from the next frame on.
+    pub fn set_behavior(&mut self, behavior: Behavior, state: BehaviorState) {
+        self.runner.animator().set_behavior(behavior, state);
+        self.runner.reschedule();
+        self.request_frame();
+    }
+}
+
+impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr + Clone, RootAnimator: Animator<DrawContext<Color, String>> + FaceControl> Avatar<'a, Color, String, RootAnimator> {
+    /// Moves the gaze smoothly to the normalized position `horizontal`, `vertical` (-1.0..=1.0, positive is right and
+    /// down) and suppresses random saccades until the target has not been updated for the hold time.
+    pub fn look_at(&mut self, horizontal: f32, vertical: f32) {
+        self.runner.animator().look_at(horizontal, vertical);
+        self.runner.reschedule();
+        self.request_frame();
+    }
+    /// Same as `look_at`, but aims the eyes at `point` in the coordinates of the draw target. Points outside of the
+    /// viewport are clamped to its edges.
+    pub fn look_at_point(&mut self, point: Point) {
+        let transform = self.orientation_transform();
+        let area = transform.logical_area();
+        let point = transform.logical_point(point);
+        let center = area.center();
+        let horizontal = (point.x - center.x) as f32 / (area.size.width.max(2) / 2) as f32;
+        let vertical = (point.y - center.y) as f32 / (area.size.height.max(2) / 2) as f32;
+        self.look_at(horizontal, vertical);
+    }
+    /// Stops following the gaze target and resumes the random saccades.
+    pub fn release_gaze(&mut self) {
+        self.runner.animator().release_gaze();
+        self.runner.reschedule();
+        self.request_frame();
+    }
+    /// Starts a blink at the next frame unless the eyes are already blinking.
+    pub fn force_blink(&mut self) {
+        self.runner.animator().force_blink();
+        self.runner.reschedule();
+        self.request_frame();
+    }
+    /// Closes the eye on the left (`is_left`) or right side of the screen for a moment.
+    pub fn wink(&mut self, is_left: bool) {
+        self.runner.animator().wink(is_left);
+        self.runner.reschedule();
+        self.request_frame();
+    }
+    /// Shows `text` in the balloon and moves the mouth along it. The balloon is cleared when the speech is over.
+    pub fn say(&mut self, text: &str) {
+        BalloonContext::set_text(self.context(), Some(text));
+        self.start_speech();
+    }
+    /// Speaks the text which is already in the balloon from its beginning.
+    pub fn start_speech(&mut self) {
+        self.runner.animator().start_speech();
+        self.runner.reschedule();
+        self.request_frame();
+    }
+}
+
+impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr + Clone, RootAnimator: Animator<DrawContext<Color, String>>> Avatar<'a, Color, String, RootAnimator> {
+    /// Creates an avatar which is animated by `animator` instead of the built-in [`FaceAnimator`].
+    pub fn with_animator(context: DrawContext<Color, String>, frames_per_second: u64, animator: RootAnimator) -> Self {
         Self {
-            last_time: None,
+            next_frame: None,
             frames_per_second,
+            frame_policy: FramePolicy::default(),
+            viewport: REFERENCE_BOUNDING_RECT,
+            orientation: Orientation::default(),
             face: Face::default(),
             effect: Effect::new(),
             balloon: Balloon::new(),
-            runner: AnimationRunner::new(context, frames_per_second, FaceAnimator::new()),
+            runner: AnimationRunner::new(context, frames_per_second, animator),
+            previous_face: None,
+            previous_effect: None,
+            previous_balloon: None,
+            previous_balloon_text: None,
+            is_balloon_visible: false,
+            frame_number: 0,
+            damage: DamageList::new(),
+            #[cfg(feature = "alloc")]
+            scratch: Vec::new(),
         }
     }
     pub fn context(&mut self) -> &mut DrawContext<Color, String> {
         self.runner.context()
     }
-    pub fn run<D: DrawTarget<Color = Color>, T: Timer>(&mut self, draw_target: &mut D, timer: &T) -> Result<(), <D as DrawTarget>::Error> {
-        let now = timer.timestamp_milliseconds();
-        let last_time = self.last_time.unwrap_or(now);
-        let next_time = last_time.wrapping_add(1000 / self.frames_per_second);
-        if next_time >= last_time {
-            self.last_time = Some(now);
-            self.runner.next();
-            self.face.render(Rectangle::zero(), self.runner.context())
-                .draw(draw_target)?;
-            self.effect.render(Rectangle::zero(), self.runner.context())
-                .draw(draw_target)?;
-            self.balloon.render(Rectangle::zero(), self.runner.context())
-                .draw(draw_target)?;
+    /// Blends to `expression` from the next frame on.
+    pub fn set_expression(&mut self, expression: Expression) {
+        self.context().expression = expression;
+        self.runner.reschedule();
+        self.request_frame();
+    }
+    /// Blends the mouth to `viseme` from the next frame on. `None` draws the mouth from the open ratio again.
+    pub fn set_viseme(&mut self, viseme: Option<Viseme>) {
+        self.context().viseme = viseme;
+        self.runner.reschedule();
+        self.request_frame();
+    }
+    /// Root animator. It runs at the next frame even if it is not due yet, so that changes take effect immediately.
+    pub fn animator(&mut self) -> &mut RootAnimator {
+        self.runner.reschedule();
+        self.request_frame();
+        self.runner.animator()
+    }
+    pub fn animator_ref(&self) -> &RootAnimator {
+        self.runner.animator_ref()
+    }
+    pub fn frame_policy(&self) -> FramePolicy {
+        self.frame_policy
+    }
+    pub fn set_frame_policy(&mut self, frame_policy: FramePolicy) {
+        self.frame_policy = frame_policy;
+    }
+    pub fn viewport(&self) -> Rectangle {
+        self.viewport
+    }
+    /// Moves the avatar to `viewport`. Nothing is drawn outside of it. The previous area is not cleared.
+    pub fn set_viewport(&mut self, viewport: Rectangle) {
+        self.viewport = viewport;
+        self.invalidate();
+        self.request_frame();
+    }
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+    /// Rotates and mirrors the avatar within the viewport. The viewport itself is not changed, and the previous
+    /// image is not cleared.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.orientation = orientation;
+        self.invalidate();
+        self.request_frame();
+    }
+    fn orientation_transform(&self) -> OrientationTransform {
+        OrientationTransform::new(self.orientation, self.viewport)
+    }
+    /// Layout of the avatar in the rotated coordinates of the current orientation.
+    pub fn layout(&self) -> Layout {
+        Layout {
+            viewport: self.orientation_transform().logical_area(),
+            scale: self.face.scale(),
+        }
+    }
+    /// Moves the avatar to the viewport of `layout` and scales all components by its scale factor.
+    ///
+    /// The viewport is given in the rotated coordinates of the current orientation, e.g. `Layout::STICKC_PLUS`
+    /// with `Rotation::Deg90` fills a 135x240 display. Set the orientation first.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.face.set_scale(layout.scale);
+        self.effect.set_scale(layout.scale);
+        self.balloon.set_scale(layout.scale);
+        let viewport = layout.viewport;
+        self.set_viewport(Rectangle::new(viewport.top_left, self.orientation.logical_size(viewport.size)));
+    }
+    /// Draws both eyes in `style` from the next frame on.
+    pub fn set_eye_style(&mut self, style: EyeStyle) {
+        self.face.set_eye_style(style);
+        self.request_frame();
+    }
+    /// Draws both eyes with `shape` from the next frame on, e.g. `&HeartEye`.
+    pub fn set_eye_shape(&mut self, shape: &'a dyn EyeShape) {
+        self.face.set_eye_shape(shape);
+        self.request_frame();
+    }
+    /// Draws the eye on the left side of the screen with `left` and the other one with `right` from the next frame on.
+    pub fn set_eye_shapes(&mut self, left: &'a dyn EyeShape, right: &'a dyn EyeShape) {
+        self.face.set_eye_shapes(left, right);
+        self.request_frame();
+    }
+    /// Restarts the random generator of the animations from `seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.runner.context().set_seed(seed);
+    }
+    /// Makes the animation depend only on `seed`, the rendered frames and the applied commands, so that a session can be
+    /// replayed with a [`crate::Player`]. Call it on a freshly created avatar before the first frame.
+    ///
+    /// The animation advances by one frame period per rendered frame (`FramePolicy::Stretch`) regardless of the timer.
+    pub fn set_deterministic(&mut self, seed: u64) {
+        self.set_seed(seed);
+        self.frame_policy = FramePolicy::Stretch;
+    }
+    /// Number of frames rendered so far.
+    pub fn frame_number(&self) -> u64 {
+        self.frame_number
+    }
+    /// Removes and returns the oldest event which happened during the calls to `run` so far.
+    pub fn poll_event(&mut self) -> Option<AnimationEvent> {
+        self.runner.context().events.pop()
+    }
+    /// Regions of the draw target updated by the last call to `run`.
+    pub fn damage(&self) -> &[Rectangle] {
+        self.damage.as_slice()
+    }
+    /// Forces the next frame to redraw every component, e.g. after the display has been cleared.
+    pub fn invalidate(&mut self) {
+        self.previous_face = None;
+        self.previous_effect = None;
+        self.previous_balloon = None;
+    }
+    /// Worst-case size of the scratch buffer passed to `run_with_scratch` for the current layout.
+    pub fn scratch_size(&self) -> usize {
+        let bounding_rect = self.orientation_transform().logical_area();
+        let head = self.runner.context_ref().head;
+        self.face.scratch_size(bounding_rect, &head)
+            .max(self.effect.scratch_size(bounding_rect, &head))
+            .max(self.balloon.scratch_size(bounding_rect))
+    }
+    /// Time at which the next frame should be rendered, or `None` if it should be rendered immediately.
+    ///
+    /// Frames before the next scheduled animation step would look the same as the current one, so this is the later of
+    /// the next frame time and the animation step.
+    pub fn next_frame_time(&self) -> Option<Instant> {
+        self.next_frame_counter().map(|frame| frame.time())
+    }
+    fn next_frame_counter(&self) -> Option<FrameCounter> {
+        let next_frame = self.next_frame?;
+        match (self.frame_policy, self.runner.scheduled()) {
+            // With Stretch, the animation runs on its own time base which cannot be compared with the timer.
+            (FramePolicy::Stretch, _) => Some(next_frame),
+            (_, Some(scheduled)) if scheduled.is_after(&next_frame) => Some(scheduled),
+            _ => Some(next_frame),
+        }
+    }
+    /// Makes the next call to `run` render regardless of the frame schedule, e.g. after the context has been changed.
+    pub fn request_frame(&mut self) {
+        self.next_frame = None;
+    }
+    /// Renders a frame if it is due, using a scratch buffer owned by the avatar.
+    #[cfg(feature = "alloc")]
+    pub fn run<D: DrawTarget<Color = Color>, T: Timer>(&mut self, draw_target: &mut D, timer: &T) -> Result<FrameStats, <D as DrawTarget>::Error> {
+        let mut scratch = core::mem::take(&mut self.scratch);
+        scratch.resize(self.scratch_size(), 0);
+        let result = self.run_with_scratch(draw_target, timer, &mut scratch);
+        self.scratch = scratch;
+        result
+    }
+    /// Renders a frame if it is due, carving the sprite buffers from `scratch`.
+    ///
+    /// `scratch` should be at least `scratch_size()` bytes long. Parts which do not fit are drawn directly to the target.
+    pub fn run_with_scratch<D: DrawTarget<Color = Color>, T: Timer>(&mut self, draw_target: &mut D, timer: &T, scratch: &mut [u8]) -> Result<FrameStats, <D as DrawTarget>::Error> {
+        self.damage.clear();
+        let start = timer.timestamp_milliseconds();
+        let now = Instant::from_millis(start);
+        // The frames are scheduled with `after_frames`, like the animators, so that an animator which runs every frame
+        // comes due exactly at each frame instead of drifting against a period rounded to milliseconds.
+        let runner_counter = self.runner.counter();
+        let next_frame = *self.next_frame.get_or_insert_with(|| runner_counter.at(now));
+        if !now.is_after(&next_frame.time()) {
+            return Ok(FrameStats::default());
+        }
+        // Frames skipped while no animation step was scheduled are not counted as dropped.
+        let next_frame = self.next_frame_counter().filter(|frame| now.is_after(&frame.time())).unwrap_or(next_frame);
+        let missed_frames = next_frame.frames_until(now);
+        let frames_dropped = match self.frame_policy {
+            FramePolicy::Drop => {
+                self.runner.next_frame(next_frame.after_frames(missed_frames));
+                missed_frames
+            },
+            FramePolicy::CatchUp { max_frames } => {
+                let steps = (missed_frames + 1).min(max_frames.max(1) as u64);
+                let frames_dropped = missed_frames + 1 - steps;
+                for step in 0..steps {
+                    self.runner.next_frame(next_frame.after_frames(frames_dropped + step));
+                }
+                frames_dropped
+            },
+            FramePolicy::Stretch => {
+                self.runner.next();
+                0
+            },
+        };
+        self.next_frame = Some(match self.frame_policy {
+            FramePolicy::Stretch => FrameCounter::new(self.frames_per_second, now).after_frames(1),
+            _ => next_frame.after_frames(missed_frames + 1),
+        });
+        let background_color = self.runner.context().palette.get_color(&BasicPaletteKey::Background);
+        let viewport = self.viewport;
+        let transform = self.orientation_transform();
+        let logical_viewport = transform.logical_area();
+        let draw_target = &mut draw_target.clipped(&viewport);
+        let face = self.face.render(logical_viewport, self.runner.context());
+        let effect = self.effect.render(logical_viewport, self.runner.context());
+        let balloon = self.balloon.render(logical_viewport, self.runner.context());
+        let balloon_snapshot = balloon.snapshot();
+        let is_text_changed = balloon.text() != self.previous_balloon_text.as_ref().map(|text| text.as_ref());
+
+        // A hidden balloon leaves its last image on the target, so erase it before drawing the parts below it.
+        if let Some(previous_balloon) = self.previous_balloon.filter(|b| b.is_visible() && !balloon.is_visible()) {
+            draw_target.fill_solid(&transform.rectangle(&previous_balloon.bounding_box()), background_color)?;
+            self.damage.push(previous_balloon.bounding_box());
+        }
+        if let Some(previous_face) = &self.previous_face {
+            face.erase_uncovered(previous_face, draw_target, &mut self.damage, &transform)?;
+        }
+        if let Some(previous_effect) = &self.previous_effect {
+            erase_uncovered(&effect, previous_effect, draw_target, &mut self.damage, background_color, &transform)?;
         }
-        Ok(())
+        face.draw_damaged(self.previous_face.as_ref(), draw_target, &mut self.damage, scratch, &transform)?;
+        draw_damaged(&effect, self.previous_effect.as_ref(), draw_target, &mut self.damage, scratch, &transform)?;
+        if balloon.is_visible() && (is_text_changed || self.previous_balloon != Some(balloon_snapshot) || self.damage.intersects(&balloon_snapshot.bounding_box())) {
+            draw_with_scratch_oriented(&balloon, draw_target, scratch, &transform)?;
+            self.damage.push(balloon_snapshot.bounding_box());
+        }
+        self.damage.clip(&logical_viewport);
+        self.damage.transform(&transform);
+        self.previous_face = Some(face);
+        self.previous_effect = Some(effect);
+        self.previous_balloon = Some(balloon_snapshot);
+        if is_text_changed {
+            self.previous_balloon_text = self.runner.context().text.clone();
+        }
+        if balloon_snapshot.is_visible() != self.is_balloon_visible {
+            self.is_balloon_visible = balloon_snapshot.is_visible();
+            self.runner.context().push_event(if self.is_balloon_visible { AnimationEvent::BalloonShown } else { AnimationEvent::BalloonHidden });
+        }
+        self.frame_number = self.frame_number.wrapping_add(1);
+        let render_milliseconds = timer.timestamp_milliseconds().wrapping_sub(start);
+        Ok(FrameStats {
+            rendered: true,
+            frames_dropped: frames_dropped.min(u32::MAX as u64) as u32,
+            render_milliseconds,
+        })
     }
 }
 
-impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw> + Default, String: AsRef<str> + FromStr> Default for Avatar<'a, Color, String> {
+impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw> + Default, String: AsRef<str> + FromStr + Clone> Default for Avatar<'a, Color, String> {
     fn default() -> Self {
         Self::new(Default::default(), 30)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use embedded_graphics::{pixelcolor::BinaryColor, prelude::{OriginDimensions, Pixel, Size}};
+
+    use super::*;
+    use crate::Rotation;
+
+    /// Timer which only moves when the test moves it, and by `step` milliseconds each time it is read.
+    struct MockTimer {
+        now: Cell<u64>,
+        step: u64,
+    }
+
+    impl MockTimer {
+        fn new(now: u64) -> Self {
+            Self { now: Cell::new(now), step: 0 }
+        }
+        fn set(&self, now: u64) {
+            self.now.set(now);
+        }
+    }
+
+    impl Timer for MockTimer {
+        fn timestamp_milliseconds(&self) -> u64 {
+            let now = self.now.get();
+            self.now.set(now + self.step);
+            now
+        }
+    }
+
+    struct Framebuffer {
+        size: Size,
+        pixels: Vec<BinaryColor>,
+    }
+
+    impl Framebuffer {
+        fn new(size: Size) -> Self {
+            Self { size, pixels: alloc::vec![BinaryColor::Off; (size.width * size.height) as usize] }
+        }
+    }
+
+    impl OriginDimensions for Framebuffer {
+        fn size(&self) -> Size {
+            self.size
+        }
+    }
+
+    impl DrawTarget for Framebuffer {
+        type Color = BinaryColor;
+        type Error = Infallible;
+        fn draw_iter<I: IntoIterator<Item = Pixel<BinaryColor>>>(&mut self, pixels: I) -> Result<(), Self::Error> {
+            for Pixel(point, color) in pixels {
+                if point.x >= 0 && point.y >= 0 && (point.x as u32) < self.size.width && (point.y as u32) < self.size.height {
+                    self.pixels[(point.y as u32 * self.size.width + point.x as u32) as usize] = color;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn context() -> DrawContext<BinaryColor, String> {
+        let mut context = DrawContext::default();
+        context.palette.set_color(&BasicPaletteKey::Primary, BinaryColor::On);
+        context.palette.set_color(&BasicPaletteKey::Background, BinaryColor::Off);
+        context
+    }
+
+    /// Runs on every frame it is called at.
+    struct EveryFrame {
+        runs: u32,
+    }
+
+    impl Animator<DrawContext<BinaryColor, String>> for EveryFrame {
+        fn next(&mut self, counter: FrameCounter, _context: &mut DrawContext<BinaryColor, String>) -> FrameCounter {
+            self.runs += 1;
+            counter.after_frames(1)
+        }
+    }
+
+    #[test]
+    fn animator_scheduled_every_frame_runs_on_every_frame() {
+        // 1000 / 30 is not a whole number of milliseconds, so the frames are 33, 33 and 34 ms apart in turn.
+        let mut avatar = Avatar::with_animator(context(), 30, EveryFrame { runs: 0 });
+        let mut display = Framebuffer::new(Size::new(320, 240));
+        let timer = MockTimer::new(1000);
+        let mut rendered = 0;
+        for now in 1001..=4000 {
+            timer.set(now);
+            if avatar.run_with_scratch(&mut display, &timer, &mut []).unwrap().rendered {
+                rendered += 1;
+            }
+        }
+        assert_eq!(rendered, 90);
+        assert_eq!(avatar.animator_ref().runs, 90);
+    }
+
+    /// Renders the first frame at 1000 ms and the next one at `late`, where the frames of 1033 to 1166 ms are due.
+    fn run_late(frame_policy: FramePolicy, late: u64) -> (Avatar<'static, BinaryColor, String, EveryFrame>, FrameStats) {
+        let mut avatar = Avatar::with_animator(context(), 30, EveryFrame { runs: 0 });
+        avatar.set_frame_policy(frame_policy);
+        let mut display = Framebuffer::new(Size::new(320, 240));
+        let timer = MockTimer::new(1000);
+        assert!(avatar.run_with_scratch(&mut display, &timer, &mut []).unwrap().rendered);
+        timer.set(late);
+        let stats = avatar.run_with_scratch(&mut display, &timer, &mut []).unwrap();
+        (avatar, stats)
+    }
+
+    #[test]
+    fn drop_renders_once_and_keeps_the_frame_schedule() {
+        let (mut avatar, stats) = run_late(FramePolicy::Drop, 1170);
+        assert!(stats.rendered);
+        // The frame of 1033 ms is rendered late and the ones of 1066 to 1166 ms are dropped.
+        assert_eq!(stats.frames_dropped, 4);
+        assert_eq!(avatar.animator_ref().runs, 2);
+        assert_eq!(avatar.next_frame_time(), Some(Instant::from_millis(1200)));
+        let mut display = Framebuffer::new(Size::new(320, 240));
+        assert!(!avatar.run_with_scratch(&mut display, &MockTimer::new(1199), &mut []).unwrap().rendered);
+        assert!(avatar.run_with_scratch(&mut display, &MockTimer::new(1200), &mut []).unwrap().rendered);
+    }
+
+    #[test]
+    fn catch_up_steps_the_missed_frames_up_to_the_limit() {
+        let (avatar, stats) = run_late(FramePolicy::CatchUp { max_frames: 3 }, 1170);
+        assert_eq!(stats.frames_dropped, 2);
+        assert_eq!(avatar.animator_ref().runs, 1 + 3);
+        assert_eq!(avatar.next_frame_time(), Some(Instant::from_millis(1200)));
+
+        let (avatar, stats) = run_late(FramePolicy::CatchUp { max_frames: 10 }, 1170);
+        assert_eq!(stats.frames_dropped, 0);
+        assert_eq!(avatar.animator_ref().runs, 1 + 5);
+    }
+
+    #[test]
+    fn stretch_steps_once_and_restarts_the_schedule() {
+        let (avatar, stats) = run_late(FramePolicy::Stretch, 1170);
+        assert!(stats.rendered);
+        assert_eq!(stats.frames_dropped, 0);
+        assert_eq!(avatar.animator_ref().runs, 2);
+        assert_eq!(avatar.next_frame_time(), Some(Instant::from_millis(1203)));
+    }
+
+    #[test]
+    fn frame_stats_report_the_render_time() {
+        let mut avatar = Avatar::with_animator(context(), 30, EveryFrame { runs: 0 });
+        let mut display = Framebuffer::new(Size::new(320, 240));
+        let timer = MockTimer { now: Cell::new(1000), step: 7 };
+        let stats = avatar.run_with_scratch(&mut display, &timer, &mut []).unwrap();
+        assert_eq!(stats, FrameStats { rendered: true, frames_dropped: 0, render_milliseconds: 7 });
+        // Reading the timer moved it to 1014 ms, before the next frame.
+        assert_eq!(avatar.run_with_scratch(&mut display, &timer, &mut []).unwrap(), FrameStats::default());
+    }
+
+    /// Calls `run` every millisecond from `now` until a frame is rendered and returns the time after that frame.
+    fn render_next(avatar: &mut Avatar<'static, BinaryColor, String, EveryFrame>, display: &mut Framebuffer, now: u64) -> u64 {
+        let mut now = now;
+        while !avatar.run_with_scratch(display, &MockTimer::new(now), &mut []).unwrap().rendered {
+            now += 1;
+        }
+        now + 1
+    }
+
+    #[test]
+    fn unchanged_frame_has_no_damage() {
+        let mut avatar = Avatar::with_animator(context(), 30, EveryFrame { runs: 0 });
+        avatar.context().text = Some(String::from("Hello"));
+        let mut display = Framebuffer::new(Size::new(320, 240));
+        let now = render_next(&mut avatar, &mut display, 1000);
+        assert!(!avatar.damage().is_empty());
+        render_next(&mut avatar, &mut display, now);
+        assert_eq!(avatar.damage(), &[]);
+    }
+
+    #[test]
+    fn blink_damages_only_the_eyes() {
+        let mut avatar = Avatar::with_animator(context(), 30, EveryFrame { runs: 0 });
+        let mut display = Framebuffer::new(Size::new(320, 240));
+        let now = render_next(&mut avatar, &mut display, 1000);
+        let open = display.pixels.clone();
+        avatar.context().eye_open_ratio = 0.0;
+        render_next(&mut avatar, &mut display, now);
+        let damage = avatar.damage();
+        // One rectangle per eye, on each side of the face and above the mouth.
+        assert_eq!(damage.len(), 2);
+        assert!(damage.iter().all(|rectangle| rectangle.bottom_right().unwrap().y < 120));
+        assert!(damage.iter().any(|rectangle| rectangle.bottom_right().unwrap().x < 160));
+        assert!(damage.iter().any(|rectangle| rectangle.top_left.x >= 160));
+        // Every pixel the blink changed is inside one of them.
+        for (index, (open, closed)) in open.iter().zip(&display.pixels).enumerate() {
+            let point = Point::new((index % 320) as i32, (index / 320) as i32);
+            if open != closed {
+                assert!(damage.iter().any(|rectangle| rectangle.contains(point)), "{point:?} is not damaged");
+            }
+        }
+    }
+
+    #[test]
+    fn damage_is_reported_in_display_coordinates_in_every_orientation() {
+        let rotations = [Rotation::Deg0, Rotation::Deg90, Rotation::Deg180, Rotation::Deg270];
+        for orientation in rotations.into_iter().flat_map(|rotation| [Orientation::new(rotation, false, false), Orientation::new(rotation, true, false)]) {
+            let mut avatar = Avatar::with_animator(context(), 30, EveryFrame { runs: 0 });
+            avatar.set_orientation(orientation);
+            let mut display = Framebuffer::new(Size::new(320, 240));
+            let now = render_next(&mut avatar, &mut display, 1000);
+            assert!(avatar.damage().iter().all(|rectangle| avatar.viewport().contains(rectangle.top_left) && avatar.viewport().contains(rectangle.bottom_right().unwrap())), "{orientation:?}");
+            let open = display.pixels.clone();
+            avatar.context().eye_open_ratio = 0.0;
+            render_next(&mut avatar, &mut display, now);
+            assert_eq!(avatar.damage().len(), 2, "{orientation:?}");
+            for (index, (open, closed)) in open.iter().zip(&display.pixels).enumerate() {
+                let point = Point::new((index % 320) as i32, (index / 320) as i32);
+                if open != closed {
+                    assert!(avatar.damage().iter().any(|rectangle| rectangle.contains(point)), "{orientation:?}: {point:?} is not damaged");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use crate::{Animator, FaceControl, FrameCounter, Instant};
+
+/// Interval at which finished or inactive combinators are polled.
+const IDLE_MILLISECONDS: u64 = 1000;
+/// Interval at which the condition of a [`Conditional`] is checked while it is false.
+const POLL_MILLISECONDS: u64 = 33;
+
+/// An animator together with the counter at which it runs next.
+#[derive(Clone, Debug)]
+struct Slot<A> {
+    animator: A,
+    scheduled: Option<FrameCounter>,
+}
+
+impl<A> Slot<A> {
+    fn new(animator: A) -> Self {
+        Self {
+            animator,
+            scheduled: None,
+        }
+    }
+    /// Runs the animator if it is due and has not finished yet.
+    fn run<Context>(&mut self, counter: FrameCounter, context: &mut Context) where A: Animator<Context> {
+        if !self.animator.is_finished() && counter.is_due(self.scheduled) {
+            self.scheduled = Some(self.animator.next(counter, context));
+        }
+    }
+    /// Counter at which the animator runs next, or `None` if it has finished.
+    fn pending<Context>(&self) -> Option<FrameCounter> where A: Animator<Context> {
+        self.scheduled.filter(|_| !self.animator.is_finished())
+    }
+    fn reset<Context>(&mut self) where A: Animator<Context> {
+        self.animator.reset();
+        self.scheduled = None;
+    }
+    /// Applies a [`FaceControl`] call to the animator and runs it at the next frame, so that the call takes effect
+    /// without waiting for the schedule the animator returned before.
+    fn control(&mut self, call: impl FnOnce(&mut A)) {
+        call(&mut self.animator);
+        self.scheduled = None;
+    }
+}
+
+/// Returns the earliest of `counters`, or an idle poll when all animators have finished.
+fn earliest<I: IntoIterator<Item = Option<FrameCounter>>>(counter: FrameCounter, counters: I) -> FrameCounter {
+    counter.earliest(counters.into_iter().flatten()).unwrap_or_else(|| counter.after_milliseconds(IDLE_MILLISECONDS))
+}
+
+/// Runs two animators side by side. Finishes when both have finished.
+#[derive(Clone, Debug)]
+pub struct Parallel<A, B> {
+    first: Slot<A>,
+    second: Slot<B>,
+}
+
+impl<A, B> Parallel<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first: Slot::new(first),
+            second: Slot::new(second),
+        }
+    }
+    pub fn first(&mut self) -> &mut A {
+        &mut self.first.animator
+    }
+    pub fn second(&mut self) -> &mut B {
+        &mut self.second.animator
+    }
+}
+
+impl<Context, A: Animator<Context>, B: Animator<Context>> Animator<Context> for Parallel<A, B> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        self.first.run(counter, context);
+        self.second.run(counter, context);
+        earliest(counter, [self.first.pending(), self.second.pending()])
+    }
+    fn is_finished(&self) -> bool {
+        self.first.animator.is_finished() && self.second.animator.is_finished()
+    }
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+}
+
+/// Runs two animators side by side. Finishes as soon as either of them has finished.
+#[derive(Clone, Debug)]
+pub struct Race<A, B> {
+    first: Slot<A>,
+    second: Slot<B>,
+}
+
+impl<A, B> Race<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first: Slot::new(first),
+            second: Slot::new(second),
+        }
+    }
+    pub fn first(&mut self) -> &mut A {
+        &mut self.first.animator
+    }
+    pub fn second(&mut self) -> &mut B {
+        &mut self.second.animator
+    }
+}
+
+impl<Context, A: Animator<Context>, B: Animator<Context>> Animator<Context> for Race<A, B> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if Animator::<Context>::is_finished(self) {
+            return counter.after_milliseconds(IDLE_MILLISECONDS);
+        }
+        self.first.run(counter, context);
+        self.second.run(counter, context);
+        if Animator::<Context>::is_finished(self) {
+            return counter.after_milliseconds(IDLE_MILLISECONDS);
+        }
+        earliest(counter, [self.first.pending(), self.second.pending()])
+    }
+    fn is_finished(&self) -> bool {
+        self.first.animator.is_finished() || self.second.animator.is_finished()
+    }
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+}
+
+/// Runs `first` until it has finished and then `second`. Finishes when `second` has finished.
+#[derive(Clone, Debug)]
+pub struct Sequence<A, B> {
+    first: Slot<A>,
+    second: Slot<B>,
+}
+
+impl<A, B> Sequence<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first: Slot::new(first),
+            second: Slot::new(second),
+        }
+    }
+    pub fn first(&mut self) -> &mut A {
+        &mut self.first.animator
+    }
+    pub fn second(&mut self) -> &mut B {
+        &mut self.second.animator
+    }
+}
+
+impl<Context, A: Animator<Context>, B: Animator<Context>> Animator<Context> for Sequence<A, B> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if !self.first.animator.is_finished() {
+            self.first.run(counter, context);
+            if !self.first.animator.is_finished() {
+                return earliest(counter, [self.first.pending()]);
+            }
+        }
+        // Start the second animator in the frame in which the first one has finished.
+        self.second.run(counter, context);
+        earliest(counter, [self.second.pending()])
+    }
+    fn is_finished(&self) -> bool {
+        self.second.animator.is_finished()
+    }
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+}
+
+/// Plays an animator `count` times, or forever if `count` is `None`. The animator is reset between the repetitions.
+#[derive(Clone, Debug)]
+pub struct Repeat<A> {
+    inner: Slot<A>,
+    count: Option<u32>,
+    done: u32,
+}
+
+impl<A> Repeat<A> {
+    pub fn new(inner: A, count: u32) -> Self {
+        Self {
+            inner: Slot::new(inner),
+            count: Some(count),
+            done: 0,
+        }
+    }
+    pub fn forever(inner: A) -> Self {
+        Self {
+            inner: Slot::new(inner),
+            count: None,
+            done: 0,
+        }
+    }
+    pub fn inner(&mut self) -> &mut A {
+        &mut self.inner.animator
+    }
+    /// Number of repetitions which have finished so far.
+    pub fn repetitions(&self) -> u32 {
+        self.done
+    }
+}
+
+impl<Context, A: Animator<Context>> Animator<Context> for Repeat<A> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if Animator::<Context>::is_finished(self) {
+            return counter.after_milliseconds(IDLE_MILLISECONDS);
+        }
+        self.inner.run(counter, context);
+        if self.inner.animator.is_finished() {
+            self.done = self.done.saturating_add(1);
+            if Animator::<Context>::is_finished(self) {
+                return counter.after_milliseconds(IDLE_MILLISECONDS);
+            }
+            // Start the next repetition in the frame in which the previous one has finished.
+            self.inner.reset();
+            self.inner.run(counter, context);
+        }
+        earliest(counter, [self.inner.pending()])
+    }
+    fn is_finished(&self) -> bool {
+        self.count.is_some_and(|count| self.done >= count)
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.done = 0;
+    }
+}
+
+/// Waits for `milliseconds` after its first run before starting the inner animator.
+#[derive(Clone, Debug)]
+pub struct Delay<A> {
+    inner: Slot<A>,
+    milliseconds: u32,
+    start: Option<Instant>,
+}
+
+impl<A> Delay<A> {
+    pub fn new(milliseconds: u32, inner: A) -> Self {
+        Self {
+            inner: Slot::new(inner),
+            milliseconds,
+            start: None,
+        }
+    }
+    pub fn inner(&mut self) -> &mut A {
+        &mut self.inner.animator
+    }
+}
+
+impl<Context, A: Animator<Context>> Animator<Context> for Delay<A> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        let start = *self.start.get_or_insert(counter.time().after_milliseconds(self.milliseconds as u64));
+        if !counter.time().is_after(&start) {
+            return counter.at(start);
+        }
+        self.inner.run(counter, context);
+        earliest(counter, [self.inner.pending()])
+    }
+    fn is_finished(&self) -> bool {
+        self.inner.animator.is_finished()
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.start = None;
+    }
+}
+
+/// Runs the inner animator only while `condition` holds for the context. The animator is paused, not reset, while
+/// the condition is false.
+#[derive(Clone, Debug)]
+pub struct Conditional<A, F> {
+    inner: Slot<A>,
+    condition: F,
+}
+
+impl<A, F> Conditional<A, F> {
+    pub fn new(condition: F, inner: A) -> Self {
+        Self {
+            inner: Slot::new(inner),
+            condition,
+        }
+    }
+    pub fn inner(&mut self) -> &mut A {
+        &mut self.inner.animator
+    }
+}
+
+impl<Context, A: Animator<Context>, F: FnMut(&Context) -> bool> Animator<Context> for Conditional<A, F> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if !(self.condition)(context) {
+            return counter.after_milliseconds(POLL_MILLISECONDS);
+        }
+        self.inner.run(counter, context);
+        earliest(counter, [self.inner.pending()])
+    }
+    fn is_finished(&self) -> bool {
+        self.inner.animator.is_finished()
+    }
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+/// Implements [`FaceControl`] for a combinator by forwarding every call to the animators in the listed slots.
+macro_rules! forward_face_control {
+    (impl<$($generic:ident $(: $bound:path)?),*> for $combinator:ty { $($slot:ident),+ }) => {
+        impl<$($generic $(: $bound)?),*> FaceControl for $combinator {
+            fn look_at(&mut self, horizontal: f32, vertical: f32) {
+                $(self.$slot.control(|animator| animator.look_at(horizontal, vertical));)+
+            }
+            fn release_gaze(&mut self) {
+                $(self.$slot.control(|animator| animator.release_gaze());)+
+            }
+            fn force_blink(&mut self) {
+                $(self.$slot.control(|animator| animator.force_blink());)+
+            }
+            fn start_speech(&mut self) {
+                $(self.$slot.control(|animator| animator.start_speech());)+
+            }
+            fn wink(&mut self, is_left: bool) {
+                $(self.$slot.control(|animator| animator.wink(is_left));)+
+            }
+        }
+    };
+}
+
+forward_face_control!(impl<A: FaceControl, B: FaceControl> for Parallel<A, B> { first, second });
+forward_face_control!(impl<A: FaceControl, B: FaceControl> for Race<A, B> { first, second });
+forward_face_control!(impl<A: FaceControl, B: FaceControl> for Sequence<A, B> { first, second });
+forward_face_control!(impl<A: FaceControl> for Repeat<A> { inner });
+forward_face_control!(impl<A: FaceControl> for Delay<A> { inner });
+forward_face_control!(impl<A: FaceControl, F> for Conditional<A, F> { inner });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnimationRunner;
+
+    /// Frames in which each scripted animator ran, as bit masks, and how often it ran.
+    #[derive(Debug, Default)]
+    struct Log {
+        frames: [u64; 2],
+        runs: [u32; 2],
+    }
+
+    /// Runs every `interval` frames and finishes after `frames` runs, or never if `frames` is zero.
+    #[derive(Clone, Debug)]
+    struct Script {
+        id: usize,
+        frames: u32,
+        interval: u64,
+        runs: u32,
+    }
+
+    impl Script {
+        fn new(id: usize, frames: u32) -> Self {
+            Self { id, frames, interval: 1, runs: 0 }
+        }
+    }
+
+    impl Animator<Log> for Script {
+        fn next(&mut self, counter: FrameCounter, context: &mut Log) -> FrameCounter {
+            self.runs += 1;
+            context.frames[self.id] |= 1 << counter.counter();
+            context.runs[self.id] += 1;
+            counter.after_frames(self.interval)
+        }
+        fn is_finished(&self) -> bool {
+            self.frames > 0 && self.runs >= self.frames
+        }
+        fn reset(&mut self) {
+            self.runs = 0;
+        }
+    }
+
+    impl FaceControl for Script {}
+
+    /// Runs `animator` at the frames 0 to `frames - 1` of a 30 fps schedule.
+    fn run<A: Animator<Log>>(animator: A, frames: u64) -> AnimationRunner<Log, A> {
+        let mut runner = AnimationRunner::new(Log::default(), 30, animator);
+        for _ in 0..frames {
+            runner.next();
+        }
+        runner
+    }
+
+    #[test]
+    fn sequence_starts_the_second_animator_in_the_frame_the_first_one_finishes() {
+        let runner = run(Sequence::new(Script::new(0, 3), Script::new(1, 2)), 10);
+        assert_eq!(runner.context_ref().frames, [0b111, 0b1100]);
+        assert!(runner.animator_ref().is_finished());
+    }
+
+    #[test]
+    fn repeat_runs_the_given_number_of_times() {
+        let runner = run(Repeat::new(Script::new(0, 2), 2), 2);
+        assert!(!runner.animator_ref().is_finished());
+        assert_eq!(runner.animator_ref().repetitions(), 1);
+
+        // The second repetition starts in frame 1 and ends in frame 2, after which nothing runs anymore.
+        let runner = run(Repeat::new(Script::new(0, 2), 2), 10);
+        assert!(runner.animator_ref().is_finished());
+        assert_eq!(runner.animator_ref().repetitions(), 2);
+        assert_eq!(runner.context_ref().frames[0], 0b111);
+        assert_eq!(runner.context_ref().runs[0], 4);
+    }
+
+    #[test]
+    fn delay_starts_the_inner_animator_when_the_delay_has_passed() {
+        // Frame 3 starts at 100 ms.
+        let runner = run(Delay::new(100, Script::new(0, 0)), 3);
+        assert_eq!(runner.context_ref().runs[0], 0);
+        let runner = run(Delay::new(100, Script::new(0, 0)), 6);
+        assert_eq!(runner.context_ref().frames[0], 0b111000);
+    }
+
+    #[test]
+    fn race_stops_the_other_animator_when_one_has_finished() {
+        let runner = run(Race::new(Script::new(0, 2), Script::new(1, 5)), 10);
+        assert!(runner.animator_ref().is_finished());
+        assert_eq!(runner.context_ref().frames, [0b11, 0b11]);
+    }
+
+    #[test]
+    fn forwarded_control_runs_the_animators_at_the_next_frame() {
+        let slow = Script { interval: 10, ..Script::new(0, 0) };
+        let mut runner = run(Parallel::new(slow.clone(), Script { id: 1, ..slow }), 1);
+        runner.animator().force_blink();
+        runner.reschedule();
+        runner.next();
+        runner.animator().look_at(0.5, 0.0);
+        runner.reschedule();
+        runner.next();
+        assert_eq!(runner.context_ref().frames, [0b111, 0b111]);
+
+        // Without a control call, the animators keep their schedule.
+        let mut runner = run(Repeat::forever(slow), 1);
+        runner.reschedule();
+        runner.next();
+        assert_eq!(runner.context_ref().frames[0], 0b1);
+    }
+}
@@ -0,0 +1,56 @@
+use core::str::FromStr;
+
+use embedded_graphics::prelude::PixelColor;
+
+use crate::{Animator, Avatar, Expression, FaceControl, Viseme};
+use crate::components::face::DrawContext;
+
+/// Input to an [`Avatar`] which can be sent from other tasks.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command<String> {
+    SetExpression(Expression),
+    SetText(Option<String>),
+    /// Shows the text in the balloon and speaks it, see [`Avatar::say`].
+    Say(String),
+    SetMouthOpenRatio(f32),
+    /// Shows a viseme, or `None` to draw the mouth from the open ratio again.
+    SetViseme(Option<Viseme>),
+    SetGaze { horizontal: f32, vertical: f32 },
+    SetPupilSize(f32),
+    /// Follows a gaze target smoothly, see [`Avatar::look_at`].
+    LookAt { horizontal: f32, vertical: f32 },
+    ReleaseGaze,
+    Blink,
+    Wink { is_left: bool },
+    Invalidate,
+    Stop,
+}
+
+impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw>, String: AsRef<str> + FromStr + Clone, RootAnimator: Animator<DrawContext<Color, String>> + FaceControl> Avatar<'a, Color, String, RootAnimator> {
+    /// Applies `command` to the avatar and requests a new frame. `Command::Stop` is ignored.
+    pub fn apply(&mut self, command: Command<String>) {
+        let context = self.context();
+        match command {
+            Command::SetExpression(expression) => self.set_expression(expression),
+            Command::SetText(text) => context.text = text,
+            Command::Say(text) => {
+                context.text = Some(text);
+                self.start_speech();
+            },
+            Command::SetMouthOpenRatio(ratio) => context.mouth_open_ratio = ratio,
+            Command::SetViseme(viseme) => self.set_viseme(viseme),
+            Command::SetGaze { horizontal, vertical } => {
+                context.gaze_horizontal = horizontal;
+                context.gaze_vertical = vertical;
+            },
+            Command::SetPupilSize(size) => context.pupil_size = size,
+            Command::LookAt { horizontal, vertical } => self.look_at(horizontal, vertical),
+            Command::ReleaseGaze => self.release_gaze(),
+            Command::Blink => self.force_blink(),
+            Command::Wink { is_left } => self.wink(is_left),
+            Command::Invalidate => self.invalidate(),
+            Command::Stop => return,
+        }
+        self.request_frame();
+    }
+}
@@ -1,6 +1,10 @@
-use embedded_graphics::{Drawable as DrawableGraphics, prelude::{PixelColor, IntoStorage}};
+#[allow(unused)]
+use micromath::F32Ext as _;
+
+use embedded_graphics::{Drawable as DrawableGraphics, draw_target::DrawTarget, prelude::{Dimensions, PixelColor, Point, Size}, primitives::{Circle, PrimitiveStyle, Rectangle, StyledDimensions, StyledDrawable, Triangle}};
 
 use crate::{Palette, BasicPaletteKey};
+use crate::util::{make_point_f32_rounded, rectangle_union};
 
 pub trait Component<'a> {
     type Drawable: DrawableGraphics;
@@ -12,4 +16,226 @@ pub trait BasicPaletteContext<'a> {
     type Color: PixelColor + From<<<Self as BasicPaletteContext<'a>>::Color as PixelColor>::Raw> + Into<<<Self as BasicPaletteContext<'a>>::Color as PixelColor>::Raw> + 'a;
     type BasicPalette: Palette<Key = BasicPaletteKey, Color = Self::Color>;
     fn get_basic_palette(&self) -> &Self::BasicPalette;
-}
\ No newline at end of file
+}
+
+/// Area which the default component geometry is designed for.
+pub const REFERENCE_BOUNDING_RECT: Rectangle = Rectangle::new(Point::zero(), Size::new(320, 240));
+
+/// Transform of the whole head: rotation angle in radians (clockwise), offset in reference pixels and scale factor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeadTransform {
+    pub angle: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub scale: f32,
+}
+
+impl HeadTransform {
+    pub const IDENTITY: Self = Self { angle: 0.0, offset_x: 0.0, offset_y: 0.0, scale: 1.0 };
+}
+
+impl Default for HeadTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Maps geometry designed for a reference area into a bounding rectangle. The geometry is scaled and rotated around
+/// the center of the reference area and then moved to the center of the bounding rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutTransform {
+    reference_center: Point,
+    center: Point,
+    scale: f32,
+    angle: f32,
+    cos: f32,
+    sin: f32,
+}
+
+impl LayoutTransform {
+    pub fn new(reference: &Rectangle, bounding_rect: &Rectangle, scale: f32) -> Self {
+        Self {
+            reference_center: reference.center(),
+            center: bounding_rect.center(),
+            scale,
+            angle: 0.0,
+            cos: 1.0,
+            sin: 0.0,
+        }
+    }
+    /// Applies `head` on top of the layout. The head offset is scaled together with the layout.
+    pub fn with_head(&self, head: &HeadTransform) -> Self {
+        Self {
+            reference_center: self.reference_center,
+            center: self.center + make_point_f32_rounded(head.offset_x * self.scale, head.offset_y * self.scale),
+            scale: self.scale * head.scale,
+            angle: head.angle,
+            cos: head.angle.cos(),
+            sin: head.angle.sin(),
+        }
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+    pub fn point(&self, point: Point) -> Point {
+        let offset = point - self.reference_center;
+        let x = offset.x as f32 * self.scale;
+        let y = offset.y as f32 * self.scale;
+        if self.angle == 0.0 {
+            return self.center + make_point_f32_rounded(x, y);
+        }
+        self.center + make_point_f32_rounded(x * self.cos - y * self.sin, x * self.sin + y * self.cos)
+    }
+    pub fn length(&self, length: u32) -> u32 {
+        (length as f32 * self.scale).round() as u32
+    }
+    pub fn rectangle(&self, rectangle: &Rectangle) -> Rectangle {
+        Rectangle::new(
+            self.point(rectangle.top_left),
+            Size::new((rectangle.size.width as f32 * self.scale).ceil() as u32, (rectangle.size.height as f32 * self.scale).ceil() as u32),
+        )
+    }
+}
+
+/// Rotation of primitives around a pivot point, used to turn the parts of a rotated head.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pivot {
+    center: Point,
+    angle: f32,
+    cos: f32,
+    sin: f32,
+}
+
+impl Pivot {
+    pub fn new(center: Point, angle: f32) -> Self {
+        Self {
+            center,
+            angle,
+            cos: angle.cos(),
+            sin: angle.sin(),
+        }
+    }
+    pub fn is_identity(&self) -> bool {
+        self.angle == 0.0
+    }
+    pub fn point(&self, point: Point) -> Point {
+        if self.is_identity() {
+            return point;
+        }
+        let offset = point - self.center;
+        let (x, y) = (offset.x as f32, offset.y as f32);
+        self.center + make_point_f32_rounded(x * self.cos - y * self.sin, x * self.sin + y * self.cos)
+    }
+    pub fn circle(&self, circle: Circle) -> Circle {
+        if self.is_identity() {
+            return circle;
+        }
+        Circle::with_center(self.point(circle.center()), circle.diameter)
+    }
+    pub fn triangle(&self, triangle: Triangle) -> Triangle {
+        let [p1, p2, p3] = triangle.vertices;
+        Triangle::new(self.point(p1), self.point(p2), self.point(p3))
+    }
+    pub fn rectangle(&self, rectangle: Rectangle) -> RotatedRectangle {
+        match rectangle.bottom_right() {
+            Some(bottom_right) if !self.is_identity() => {
+                let top_right = Point::new(bottom_right.x, rectangle.top_left.y);
+                let bottom_left = Point::new(rectangle.top_left.x, bottom_right.y);
+                let (p1, p2, p3, p4) = (self.point(rectangle.top_left), self.point(top_right), self.point(bottom_right), self.point(bottom_left));
+                RotatedRectangle::Rotated(Triangle::new(p1, p2, p3), Triangle::new(p1, p3, p4))
+            },
+            _ => RotatedRectangle::Aligned(rectangle),
+        }
+    }
+    pub fn rotated_rectangle(&self, rectangle: RotatedRectangle) -> RotatedRectangle {
+        match rectangle {
+            RotatedRectangle::Aligned(rectangle) => self.rectangle(rectangle),
+            RotatedRectangle::Rotated(t1, t2) => RotatedRectangle::Rotated(self.triangle(t1), self.triangle(t2)),
+        }
+    }
+    /// Smallest rectangle which contains `rectangle` after the rotation.
+    pub fn bounding_box(&self, rectangle: &Rectangle) -> Rectangle {
+        if self.is_identity() {
+            return *rectangle;
+        }
+        match self.rectangle(*rectangle) {
+            RotatedRectangle::Rotated(t1, t2) => rectangle_union(&t1.bounding_box(), &t2.bounding_box()),
+            RotatedRectangle::Aligned(rectangle) => rectangle,
+        }
+    }
+}
+
+/// Rectangle which may have been rotated into a pair of triangles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RotatedRectangle {
+    Aligned(Rectangle),
+    Rotated(Triangle, Triangle),
+}
+
+impl<Color: PixelColor> StyledDrawable<PrimitiveStyle<Color>> for RotatedRectangle {
+    type Color = Color;
+    type Output = ();
+    fn draw_styled<D: DrawTarget<Color = Self::Color>>(&self, style: &PrimitiveStyle<Color>, target: &mut D) -> Result<Self::Output, D::Error> {
+        match self {
+            Self::Aligned(rectangle) => rectangle.draw_styled(style, target),
+            Self::Rotated(t1, t2) => {
+                t1.draw_styled(style, target)?;
+                t2.draw_styled(style, target)
+            },
+        }
+    }
+}
+
+impl<Color: PixelColor> StyledDimensions<PrimitiveStyle<Color>> for RotatedRectangle {
+    fn styled_bounding_box(&self, style: &PrimitiveStyle<Color>) -> Rectangle {
+        match self {
+            Self::Aligned(rectangle) => rectangle.styled_bounding_box(style),
+            Self::Rotated(t1, t2) => rectangle_union(&t1.styled_bounding_box(style), &t2.styled_bounding_box(style)),
+        }
+    }
+}
+
+/// Screen area and scale factor of an avatar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub viewport: Rectangle,
+    pub scale: f32,
+}
+
+impl Layout {
+    /// M5Stack Basic, Gray, Fire, Core2 and CoreS3 (320x240), the reference geometry.
+    pub const CORE2: Self = Self::new(320, 240, 1.0);
+    /// M5StickC Plus and Cardputer in landscape orientation (240x135).
+    pub const STICKC_PLUS: Self = Self::new(240, 135, 0.5625);
+    /// M5StickC in landscape orientation (160x80).
+    pub const STICKC: Self = Self::new(160, 80, 0.333);
+    /// AtomS3 (128x128).
+    pub const ATOMS3: Self = Self::new(128, 128, 0.4);
+    /// M5Stack CoreInk (200x200).
+    pub const COREINK: Self = Self::new(200, 200, 0.625);
+
+    const fn new(width: u32, height: u32, scale: f32) -> Self {
+        Self {
+            viewport: Rectangle::new(Point::zero(), Size::new(width, height)),
+            scale,
+        }
+    }
+    /// Largest layout of the reference geometry which fits in `viewport`.
+    pub fn fit(viewport: Rectangle) -> Self {
+        let scale_x = viewport.size.width as f32 / REFERENCE_BOUNDING_RECT.size.width as f32;
+        let scale_y = viewport.size.height as f32 / REFERENCE_BOUNDING_RECT.size.height as f32;
+        Self {
+            viewport,
+            scale: scale_x.min(scale_y),
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::CORE2
+    }
+}
@@ -1,4 +1,5 @@
 pub mod eye;
+pub mod eye_shape;
 pub mod eyeblow;
 pub mod mouth;
 pub mod face;
@@ -1,11 +1,14 @@
 use core::marker::PhantomData;
+#[allow(unused)]
+use micromath::F32Ext as _;
 
-use embedded_graphics::{mono_font::{ascii::FONT_10X20, MonoTextStyle}, prelude::{Drawable as DrawableGraphics, PixelColor, Point, Size}, primitives::{Ellipse, Line, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, StyledDrawable, Triangle}, text::{renderer::TextRenderer, Text}};
+use embedded_graphics::{mono_font::{ascii::{FONT_10X20, FONT_6X10}, MonoTextStyle}, prelude::{Drawable as DrawableGraphics, DrawTarget, PixelColor, Point, Size, Dimensions}, primitives::{Ellipse, Line, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, StyledDrawable}, text::{renderer::TextRenderer, Text}};
 
-use crate::{sprite::Sprite, BasicPaletteContext, Component, Palette, BasicPaletteKey, util::{make_ellipse_at_ceter_with_size, prepare_sprite_buffer}};
+use crate::{sprite::{SpriteDrawable, draw_buffered}, BasicPaletteContext, Component, Palette, BasicPaletteKey, REFERENCE_BOUNDING_RECT, LayoutTransform, util::{make_ellipse_at_ceter_with_size, scratch_size_for}};
 
 
 pub struct DrawableBalloon<'a, Color: PixelColor, TextStyle> {
+    background_color: Color,
     style: PrimitiveStyle<Color>,
     text: Option<Text<'a, TextStyle>>,
     ellipse_outer: Option<Ellipse>,
@@ -14,11 +17,70 @@ pub struct DrawableBalloon<'a, Color: PixelColor, TextStyle> {
     bounding_box: Rectangle,
 }
 
+/// Owned summary of the geometry of a [`DrawableBalloon`], used to compare balloons across frames. The text is compared separately.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BalloonSnapshot<Color: PixelColor> {
+    background_color: Color,
+    style: PrimitiveStyle<Color>,
+    text_position: Option<Point>,
+    ellipse_outer: Option<Ellipse>,
+    bounding_box: Rectangle,
+}
+
+impl<Color: PixelColor> BalloonSnapshot<Color> {
+    pub fn is_visible(&self) -> bool {
+        self.text_position.is_some()
+    }
+    pub fn bounding_box(&self) -> Rectangle {
+        self.bounding_box
+    }
+}
+
+impl<'a, Color: PixelColor, TextStyle> DrawableBalloon<'a, Color, TextStyle> {
+    pub fn is_visible(&self) -> bool {
+        self.text.is_some()
+    }
+    pub fn text(&self) -> Option<&'a str> {
+        self.text.as_ref().map(|text| text.text)
+    }
+    pub fn snapshot(&self) -> BalloonSnapshot<Color> {
+        BalloonSnapshot {
+            background_color: self.background_color,
+            style: self.style,
+            text_position: self.text.as_ref().map(|text| text.position),
+            ellipse_outer: self.ellipse_outer,
+            bounding_box: self.bounding_box,
+        }
+    }
+}
+
+impl<'a, Color: PixelColor, TextStyle> Dimensions for DrawableBalloon<'a, Color, TextStyle> {
+    fn bounding_box(&self) -> Rectangle {
+        self.bounding_box
+    }
+}
+
 pub trait BalloonContext<'a> : BasicPaletteContext<'a> {
     fn text(&self) -> Option<&str>;
     fn set_text(&mut self, string: Option<&str>);
 }
 
+impl<'a, Color: PixelColor, TextStyle: TextRenderer<Color = Color>> SpriteDrawable for DrawableBalloon<'a, Color, TextStyle> {
+    type Color = Color;
+    fn background_color(&self) -> Self::Color {
+        self.background_color
+    }
+    fn draw_sprite<D: DrawTarget<Color = Self::Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        if let (Some(ellipse_outer), Some(line_left), Some(line_right), Some(text)) = (&self.ellipse_outer, &self.line_left, &self.line_right, &self.text) {
+            ellipse_outer.draw_styled(&self.style, target)?;
+            line_left.draw_styled(&self.style, target)?;
+            line_right.draw_styled(&self.style, target)?;
+            text.draw(target)?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a, Color: PixelColor + Into<Color::Raw> + From<Color::Raw>, TextStyle: TextRenderer<Color = Color>> DrawableGraphics for DrawableBalloon<'a, Color, TextStyle> 
 {
     type Color = Color;
@@ -26,32 +88,47 @@ impl<'a, Color: PixelColor + Into<Color::Raw> + From<Color::Raw>, TextStyle: Tex
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        match (&self.ellipse_outer, &self.line_left, &self.line_right, &self.text) {
-            (Some(ellipse_outer), Some(line_left), Some(line_right), Some(text)) => {
-                let mut buffer = prepare_sprite_buffer::<Color>(self.bounding_box);
-                let mut sprite = Sprite::<Color>::new_unaligned(&mut buffer, self.bounding_box).unwrap();
-                ellipse_outer.draw_styled(&self.style, &mut sprite).ok();
-                line_left.draw_styled(&self.style, &mut sprite).ok();
-                line_right.draw_styled(&self.style, &mut sprite).ok();
-                text.draw(&mut sprite).ok();
-                sprite.draw(target)?;
-            },
-            _ => {}
+        if self.is_visible() {
+            draw_buffered(self, target)?;
         }
         Ok(())
     }
 }
 
 pub struct Balloon<'a, Context: BalloonContext<'a>> {
+    scale: f32,
     context: PhantomData<&'a Context>,
 }
 
 impl <'a, Context: BalloonContext<'a>> Balloon<'a, Context> {
     pub fn new() -> Self {
         Self {
+            scale: 1.0,
             context: PhantomData {},
         }
     }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    /// Sets the scale factor of the balloon. A smaller font is used below 0.75.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+    pub fn bounding_box(&self, bounding_rect: Rectangle) -> Rectangle {
+        let transform = LayoutTransform::new(&REFERENCE_BOUNDING_RECT, &bounding_rect, self.scale);
+        let cy = 220;
+        transform.rectangle(&Rectangle::new(Point::new(0, cy - 42), Size::new(320, 240 - (cy - 42) as u32)))
+    }
+    /// Size of the scratch buffer required to render this component.
+    pub fn scratch_size(&self, bounding_rect: Rectangle) -> usize {
+        scratch_size_for::<Context::Color>(&[self.bounding_box(bounding_rect)])
+    }
+}
+
+impl <'a, Context: BalloonContext<'a>> Default for Balloon<'a, Context> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl <'a, Context: BalloonContext<'a>> Component<'a> for Balloon<'a, Context> 
@@ -59,29 +136,33 @@ impl <'a, Context: BalloonContext<'a>> Component<'a> for Balloon<'a, Context>
 {
     type Context = Context;
     type Drawable = DrawableBalloon<'a, Context::Color, MonoTextStyle<'static, Context::Color>>;
-    fn render(&self, _bounding_rect: Rectangle, context: &'a Self::Context) -> Self::Drawable {
+    fn render(&self, bounding_rect: Rectangle, context: &'a Self::Context) -> Self::Drawable {
         let foreground_color = context.get_basic_palette().get_color(&BasicPaletteKey::BalloonForeground);
+        let background_color = context.get_basic_palette().get_color(&BasicPaletteKey::Background);
         let style = PrimitiveStyleBuilder::new()
             .stroke_color(foreground_color)
             .stroke_width(1)
             .build();
-        let cx = 240;
-        let cy = 220;
-        let bounding_box = Rectangle::new(Point::new(0, cy - 42), Size::new(320, 240 - (cy - 42) as u32));
+        let transform = LayoutTransform::new(&REFERENCE_BOUNDING_RECT, &bounding_rect, self.scale);
+        let center = transform.point(Point::new(240, 220));
+        let (cx, cy) = (center.x, center.y);
+        let d = |length: i32| (length as f32 * self.scale).round() as i32;
+        let bounding_box = self.bounding_box(bounding_rect);
         if let Some(text) = context.text() {
-            let font = &FONT_10X20;
+            let font = if self.scale < 0.75 { &FONT_6X10 } else { &FONT_10X20 };
             let baseline = font.baseline;
             let spacing_width = if text.is_empty() { 0 } else { font.character_spacing * (text.len() as u32 - 1) };
             let text_width = font.character_size.width * (text.len() as u32) + spacing_width;
             let text_height = font.character_size.height;
-            let ellipse_outer = Some(make_ellipse_at_ceter_with_size(cx - 20, cy, text_width + 12, text_height * 2 + 2));
-            let balloon_top = Point::new(cx - 62, cy - 42);
-            let line_left = Some(Line::new(balloon_top.clone(), Point::new(cx - 43, cy - 20)));
-            let line_right = Some(Line::new(balloon_top.clone(), Point::new(cx - 8, cy - 21)));
+            let ellipse_outer = Some(make_ellipse_at_ceter_with_size(cx - d(20), cy, text_width + d(12) as u32, text_height * 2 + 2));
+            let balloon_top = Point::new(cx - d(62), cy - d(42));
+            let line_left = Some(Line::new(balloon_top, Point::new(cx - d(43), cy - d(20))));
+            let line_right = Some(Line::new(balloon_top, Point::new(cx - d(8), cy - d(21))));
             
             let character_style = MonoTextStyle::new(font, foreground_color);
-            let text = Text::new(text, Point::new(cx - text_width as i32 / 2 - 20, cy + (baseline - text_height / 2) as i32), character_style);
+            let text = Text::new(text, Point::new(cx - text_width as i32 / 2 - d(20), cy + (baseline - text_height / 2) as i32), character_style);
             Self::Drawable {
+                background_color,
                 style,
                 text: Some(text),
                 ellipse_outer,
@@ -91,6 +172,7 @@ impl <'a, Context: BalloonContext<'a>> Component<'a> for Balloon<'a, Context>
             }
         } else {
             Self::Drawable {
+                background_color,
                 style,
                 text: None,
                 ellipse_outer: None,
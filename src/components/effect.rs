@@ -1,18 +1,18 @@
 use core::marker::PhantomData;
-use micromath::F32Ext;
 #[allow(unused)]
 use micromath::F32Ext as _;
 
-use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget};
+use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget, Dimensions};
 use embedded_graphics::Drawable as DrawableGraphics;
-use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Circle, Rectangle, Primitive, Triangle};
-use crate::sprite::Sprite;
-use crate::util::{make_point_f32_rounded, make_size_f32_rounded, make_circle_center_radius, rectangle_union_all, prepare_sprite_buffer};
-use crate::{BasicPaletteContext, ExpressionContext, Expression};
+use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Circle, Rectangle, Primitive, StyledDrawable, Triangle};
+use crate::sprite::{SpriteDrawable, draw_buffered};
+use crate::util::{make_point_f32_rounded, make_circle_center_radius, rectangle_union, rectangle_union_all, scratch_size_for};
+use crate::{BasicPaletteContext, ExpressionContext, REFERENCE_BOUNDING_RECT, LayoutTransform, HeadTransform, Pivot, RotatedRectangle};
 use crate::component::Component;
 use crate::palette::{Palette, BasicPaletteKey};
 use super::mouth::MouthContext;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableBubbleMark<Color: PixelColor> {
     style: PrimitiveStyle<Color>,
     main_circle: Circle,
@@ -29,7 +29,7 @@ impl<Color: PixelColor> DrawableBubbleMark<Color> {
         let x = geometry.position.x;
         let y = geometry.position.y;
         let r = geometry.size;
-        let r = r as f32 + ((r as f32) * 0.2 * (offset as f32)).floor();
+        let r = r as f32 + ((r as f32) * 0.2 * offset).floor();
         let r_small = (r / 4.0).round();
         Self {
             style,
@@ -37,6 +37,13 @@ impl<Color: PixelColor> DrawableBubbleMark<Color> {
             small_circle: Circle::new(make_point_f32_rounded(x as f32 - r_small, y as f32 - r_small), (r_small * 2.0) as u32),
         }
     }
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        Self {
+            style: self.style,
+            main_circle: pivot.circle(self.main_circle),
+            small_circle: pivot.circle(self.small_circle),
+        }
+    }
     pub fn bounding_box(geometry: &EffectGeometry) -> Rectangle {
         let x = geometry.position.x as f32;
         let y = geometry.position.y as f32;
@@ -63,12 +70,13 @@ impl<Color: PixelColor> DrawableGraphics for DrawableBubbleMark<Color> {
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        self.main_circle.into_styled(self.style.clone()).draw(target)?;
-        self.small_circle.into_styled(self.style.clone()).draw(target)?;
+        self.main_circle.into_styled(self.style).draw(target)?;
+        self.small_circle.into_styled(self.style).draw(target)?;
         Ok(())
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableSweatMark<Color: PixelColor> {
     style: PrimitiveStyle<Color>,
     circle: Circle,
@@ -86,8 +94,8 @@ impl<Color: PixelColor> DrawableSweatMark<Color> {
         let y = geometry.position.y;
         let r = geometry.size;
         let y = y + (offset * 5.0).round() as i32;
-        let r = r as f32 + ((r as f32) * 0.2 * (offset as f32)).floor();
-        let a = 1.7320508 * r / 2.0;
+        let r = r as f32 + ((r as f32) * 0.2 * offset).floor();
+        let a = 1.732_050_8 * r / 2.0;
 
         let left = (x as f32 - r).floor() as i32;
         let right = (x as f32 + r).ceil() as i32;
@@ -103,12 +111,19 @@ impl<Color: PixelColor> DrawableSweatMark<Color> {
             ),
         }
     }
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        Self {
+            style: self.style,
+            circle: pivot.circle(self.circle),
+            triangle: pivot.triangle(self.triangle),
+        }
+    }
     pub fn bounding_box(geometry: &EffectGeometry) -> Rectangle {
         let x = geometry.position.x as f32;
         let y = geometry.position.y as f32;
         let r = geometry.size as f32;
         let r = (r + r * 0.2).floor();
-        let _a = 1.7320508 * r / 2.0;
+        let _a = 1.732_050_8 * r / 2.0;
 
         let left = x - r;
         let top = y;
@@ -136,11 +151,12 @@ impl<Color: PixelColor> DrawableGraphics for DrawableSweatMark<Color> {
 }
 
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableChillMark<Color: PixelColor> {
     style: PrimitiveStyle<Color>,
-    rect0: Rectangle,
-    rect1: Rectangle,
-    rect2: Rectangle,
+    rect0: RotatedRectangle,
+    rect1: RotatedRectangle,
+    rect2: RotatedRectangle,
 }
 
 impl<Color: PixelColor> DrawableChillMark<Color> {
@@ -158,9 +174,17 @@ impl<Color: PixelColor> DrawableChillMark<Color> {
 
         Self {
             style,
-            rect0: Rectangle::new(make_point_f32_rounded(x as f32 - h_div_2, y as f32), Size::new(3, h_div_2 as u32)),
-            rect1: Rectangle::new(Point::new(x, y), Size::new(3, (h * 3.0 / 4.0) as u32)),
-            rect2: Rectangle::new(make_point_f32_rounded(x as f32 + h_div_2, y as f32), Size::new(3, h as u32)),
+            rect0: RotatedRectangle::Aligned(Rectangle::new(make_point_f32_rounded(x as f32 - h_div_2, y as f32), Size::new(3, h_div_2 as u32))),
+            rect1: RotatedRectangle::Aligned(Rectangle::new(Point::new(x, y), Size::new(3, (h * 3.0 / 4.0) as u32))),
+            rect2: RotatedRectangle::Aligned(Rectangle::new(make_point_f32_rounded(x as f32 + h_div_2, y as f32), Size::new(3, h as u32))),
+        }
+    }
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        Self {
+            style: self.style,
+            rect0: pivot.rotated_rectangle(self.rect0),
+            rect1: pivot.rotated_rectangle(self.rect1),
+            rect2: pivot.rotated_rectangle(self.rect2),
         }
     }
     pub fn bounding_box(geometry: &EffectGeometry) -> Rectangle {
@@ -189,19 +213,20 @@ impl<Color: PixelColor> DrawableGraphics for DrawableChillMark<Color> {
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        self.rect0.into_styled(self.style).draw(target)?;
-        self.rect1.into_styled(self.style).draw(target)?;
-        self.rect2.into_styled(self.style).draw(target)?;
+        self.rect0.draw_styled(&self.style, target)?;
+        self.rect1.draw_styled(&self.style, target)?;
+        self.rect2.draw_styled(&self.style, target)?;
         Ok(())
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableAngerMark<Color: PixelColor> {
     style: PrimitiveStyle<Color>,
-    rect0: Rectangle,
-    rect1: Rectangle,
-    rect2: Rectangle,
-    rect3: Rectangle,
+    rect0: RotatedRectangle,
+    rect1: RotatedRectangle,
+    rect2: RotatedRectangle,
+    rect3: RotatedRectangle,
 }
 
 impl<Color: PixelColor> DrawableAngerMark<Color> {
@@ -220,10 +245,19 @@ impl<Color: PixelColor> DrawableAngerMark<Color> {
 
         Self {
             style,
-            rect0: Rectangle::new(Point::new(x - r_one_third, y - r), Size::new((r * 2 / 3) as u32, (r * 2) as u32)),
-            rect1: Rectangle::new(Point::new(x - r, y - r_one_third), Size::new((r * 2) as u32, (r * 2 / 3) as u32)),
-            rect2: Rectangle::new(Point::new(x - r_one_third + 2, y - r), Size::new((r * 2 / 3 - 4) as u32, (r * 2) as u32)),
-            rect3: Rectangle::new(Point::new(x - r, y - r_one_third + 2), Size::new((r * 2 / 3) as u32, (r * 2 / 3 - 4) as u32)),
+            rect0: RotatedRectangle::Aligned(Rectangle::new(Point::new(x - r_one_third, y - r), Size::new((r * 2 / 3) as u32, (r * 2) as u32))),
+            rect1: RotatedRectangle::Aligned(Rectangle::new(Point::new(x - r, y - r_one_third), Size::new((r * 2) as u32, (r * 2 / 3) as u32))),
+            rect2: RotatedRectangle::Aligned(Rectangle::new(Point::new(x - r_one_third + 2, y - r), Size::new((r * 2 / 3 - 4).max(0) as u32, (r * 2) as u32))),
+            rect3: RotatedRectangle::Aligned(Rectangle::new(Point::new(x - r, y - r_one_third + 2), Size::new((r * 2 / 3) as u32, (r * 2 / 3 - 4).max(0) as u32))),
+        }
+    }
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        Self {
+            style: self.style,
+            rect0: pivot.rotated_rectangle(self.rect0),
+            rect1: pivot.rotated_rectangle(self.rect1),
+            rect2: pivot.rotated_rectangle(self.rect2),
+            rect3: pivot.rotated_rectangle(self.rect3),
         }
     }
     pub fn bounding_box(geometry: &EffectGeometry) -> Rectangle {
@@ -250,14 +284,15 @@ impl<Color: PixelColor> DrawableGraphics for DrawableAngerMark<Color> {
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        self.rect0.into_styled(self.style).draw(target)?;
-        self.rect1.into_styled(self.style).draw(target)?;
-        self.rect2.into_styled(self.style).draw(target)?;
-        self.rect3.into_styled(self.style).draw(target)?;
+        self.rect0.draw_styled(&self.style, target)?;
+        self.rect1.draw_styled(&self.style, target)?;
+        self.rect2.draw_styled(&self.style, target)?;
+        self.rect3.draw_styled(&self.style, target)?;
         Ok(())
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableHeartMark<Color: PixelColor> {
     style: PrimitiveStyle<Color>,
     circle0: Circle,
@@ -277,7 +312,7 @@ impl<Color: PixelColor> DrawableHeartMark<Color> {
         let r = r as f32 + (r as f32 * 0.4 * offset);
         let x = geometry.position.x as f32;
         let y = geometry.position.y as f32;
-        let a = r * 1.41421356 / 4.0;
+        let a = r * core::f32::consts::SQRT_2 / 4.0;
         Self {
             style,
             circle0: make_circle_center_radius(x - r / 2.0, y, r / 2.0),
@@ -294,12 +329,21 @@ impl<Color: PixelColor> DrawableHeartMark<Color> {
             ),
         }
     }
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        Self {
+            style: self.style,
+            circle0: pivot.circle(self.circle0),
+            circle1: pivot.circle(self.circle1),
+            triangle0: pivot.triangle(self.triangle0),
+            triangle1: pivot.triangle(self.triangle1),
+        }
+    }
     pub fn bounding_box(geometry: &EffectGeometry) -> Rectangle {
         let r = geometry.size as f32;
         let r = r + (r * 0.4);
         let x = geometry.position.x as f32;
         let y = geometry.position.y as f32;
-        let a = r * 1.41421356 / 4.0;
+        let a = r * core::f32::consts::SQRT_2 / 4.0;
         let left = x - r / 2.0 - a;
         let right = x + r / 2.0 + a;
         let top = y - r / 2.0;
@@ -327,17 +371,42 @@ impl<Color: PixelColor> DrawableGraphics for DrawableHeartMark<Color> {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct EffectGeometry {
     pub position: Point, 
     pub size: u32,
 }
 
+impl EffectGeometry {
+    pub fn translate(&self, offset: Point) -> Self {
+        Self {
+            position: self.position + offset,
+            size: self.size,
+        }
+    }
+    /// Maps the geometry designed for the reference area through `transform`.
+    pub fn transform(&self, transform: &LayoutTransform) -> Self {
+        Self {
+            position: transform.point(self.position),
+            size: transform.length(self.size).max(1),
+        }
+    }
+    /// Scales the size by `factor` around the position.
+    pub fn scale(&self, factor: f32) -> Self {
+        Self {
+            position: self.position,
+            size: (self.size as f32 * factor).round() as u32,
+        }
+    }
+}
+
 pub struct Effect<'a, Context: MouthContext<'a> + BasicPaletteContext<'a> + ExpressionContext> {
     sweat_geometry: EffectGeometry,
     anger_geometry: EffectGeometry,
     heart_geometry: EffectGeometry,
     chill_geometry: EffectGeometry,
     bubble_geometries: [EffectGeometry; 2],
+    scale: f32,
     context: PhantomData<&'a Context>,
 }
 
@@ -352,11 +421,57 @@ impl<'a, Context: MouthContext<'a> + BasicPaletteContext<'a> + ExpressionContext
                 EffectGeometry { position: Point::new(290, 40), size: 10 },
                 EffectGeometry { position: Point::new(270, 52), size: 6 },
             ],
+            scale: 1.0,
             context: PhantomData{},
         }
     }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+    /// Bounding boxes of the sweat, anger, heart, chill and bubble marks, each rotated around its own position.
+    fn mark_bounding_boxes(&self, transform: &LayoutTransform) -> [Rectangle; 5] {
+        let rotated = |geometry: &EffectGeometry, bounding_box: fn(&EffectGeometry) -> Rectangle| {
+            let geometry = geometry.transform(transform);
+            Pivot::new(geometry.position, transform.angle()).bounding_box(&bounding_box(&geometry))
+        };
+        [
+            rotated(&self.sweat_geometry, DrawableSweatMark::<Context::Color>::bounding_box),
+            rotated(&self.anger_geometry, DrawableAngerMark::<Context::Color>::bounding_box),
+            rotated(&self.heart_geometry, DrawableHeartMark::<Context::Color>::bounding_box),
+            rotated(&self.chill_geometry, DrawableChillMark::<Context::Color>::bounding_box),
+            rectangle_union(
+                &rotated(&self.bubble_geometries[0], DrawableBubbleMark::<Context::Color>::bounding_box),
+                &rotated(&self.bubble_geometries[1], DrawableBubbleMark::<Context::Color>::bounding_box),
+            ),
+        ]
+    }
+    pub fn bounding_box(&self, bounding_rect: Rectangle) -> Rectangle {
+        let transform = LayoutTransform::new(&REFERENCE_BOUNDING_RECT, &bounding_rect, self.scale);
+        rectangle_union_all(&self.mark_bounding_boxes(&transform)).unwrap()
+    }
+    /// Size of the scratch buffer required to render this component with `head`.
+    pub fn scratch_size(&self, bounding_rect: Rectangle, head: &HeadTransform) -> usize {
+        let transform = LayoutTransform::new(&REFERENCE_BOUNDING_RECT, &bounding_rect, self.scale).with_head(head);
+        let bounding_boxes = self.mark_bounding_boxes(&transform);
+        if head.angle == 0.0 {
+            scratch_size_for::<Context::Color>(&[rectangle_union_all(&bounding_boxes).unwrap()])
+        } else {
+            // A rotated effect only covers the marks which are shown, but two of them are shown during a transition.
+            scratch_size_for::<Context::Color>(&[rectangle_union_all(&bounding_boxes.map(|bounding_box| bounding_box.offset(1))).unwrap()])
+        }
+    }
+}
+
+impl<'a, Context: MouthContext<'a> + BasicPaletteContext<'a> + ExpressionContext> Default for Effect<'a, Context> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum DrawableEffectMark<Color: PixelColor> {
     Sweat(DrawableSweatMark<Color>),
     Anger(DrawableAngerMark<Color>),
@@ -364,35 +479,49 @@ pub enum DrawableEffectMark<Color: PixelColor> {
     Chill(DrawableChillMark<Color>),
     Bubbles((DrawableBubbleMark<Color>, DrawableBubbleMark<Color>)),
 }
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableEffect<Color: PixelColor> {
     background_color: Color,
-    mark: Option<DrawableEffectMark<Color>>,
+    /// Marks in the order of `Effect::mark_bounding_boxes`. A mark is `None` while its size is zero.
+    marks: [Option<DrawableEffectMark<Color>>; 5],
     bounding_box: Rectangle,
 }
 
+impl<Color: PixelColor> Dimensions for DrawableEffect<Color> {
+    fn bounding_box(&self) -> Rectangle {
+        self.bounding_box
+    }
+}
+
+impl<Color: PixelColor> SpriteDrawable for DrawableEffect<Color> {
+    type Color = Color;
+    fn background_color(&self) -> Self::Color {
+        self.background_color
+    }
+    fn draw_sprite<D: DrawTarget<Color = Self::Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        for mark in self.marks.iter().flatten() {
+            match mark {
+                DrawableEffectMark::Sweat(mark) => { mark.draw(target)?; },
+                DrawableEffectMark::Anger(mark) => { mark.draw(target)?; },
+                DrawableEffectMark::Heart(mark) => { mark.draw(target)?; },
+                DrawableEffectMark::Chill(mark) => { mark.draw(target)?; },
+                DrawableEffectMark::Bubbles((mark0, mark1)) => {
+                    mark0.draw(target)?;
+                    mark1.draw(target)?;
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics for DrawableEffect<Color> {
     type Color = Color;
     type Output = ();
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        
-        let mut buffer = prepare_sprite_buffer::<Color>(self.bounding_box);
-        let mut sprite = Sprite::<Color>::new_unaligned(&mut buffer, self.bounding_box).unwrap();
-        sprite.clear(self.background_color).ok();
-        match &self.mark {
-            Some(DrawableEffectMark::Sweat(mark)) => { mark.draw(&mut sprite).ok(); },
-            Some(DrawableEffectMark::Anger(mark)) => { mark.draw(&mut sprite).ok(); },
-            Some(DrawableEffectMark::Heart(mark)) => { mark.draw(&mut sprite).ok(); },
-            Some(DrawableEffectMark::Chill(mark)) => { mark.draw(&mut sprite).ok(); },
-            Some(DrawableEffectMark::Bubbles((mark0, mark1))) => {
-                mark0.draw(&mut sprite).ok();
-                mark1.draw(&mut sprite).ok();
-            },
-            None => {},
-        }
-        sprite.draw(target)?;
-        Ok(())
+        draw_buffered(self, target)
     }
 }
 
@@ -401,37 +530,43 @@ impl<'a, Context: MouthContext<'a> + BasicPaletteContext<'a> + ExpressionContext
 {
     type Context = Context;
     type Drawable = DrawableEffect<<Context as BasicPaletteContext<'a>>::Color>;
-    fn render(&self, _bounding_rect: embedded_graphics::primitives::Rectangle, context: &'a Self::Context) -> Self::Drawable {
+    fn render(&self, bounding_rect: embedded_graphics::primitives::Rectangle, context: &'a Self::Context) -> Self::Drawable {
         let foreground_color = context.get_basic_palette().get_color(&BasicPaletteKey::Primary);
         let background_color = context.get_basic_palette().get_color(&BasicPaletteKey::Background);
-        
-        let bounding_box = rectangle_union_all(&[
-            DrawableSweatMark::<Context::Color>::bounding_box(&self.sweat_geometry),
-            DrawableAngerMark::<Context::Color>::bounding_box(&self.anger_geometry),
-            DrawableHeartMark::<Context::Color>::bounding_box(&self.heart_geometry),
-            DrawableChillMark::<Context::Color>::bounding_box(&self.chill_geometry),
-            DrawableBubbleMark::<Context::Color>::bounding_box(&self.bubble_geometries[0]),
-            DrawableBubbleMark::<Context::Color>::bounding_box(&self.bubble_geometries[1]),
-        ]).unwrap();
+        let head = context.head();
+        let transform = LayoutTransform::new(&REFERENCE_BOUNDING_RECT, &bounding_rect, self.scale).with_head(&head);
+        let bounding_boxes = self.mark_bounding_boxes(&transform);
+        let pivot = |geometry: &EffectGeometry| Pivot::new(geometry.transform(&transform).position, head.angle);
 
         let offset = context.breath();
-        let expression = context.expression();
-        let drawable_effect = match expression {
-            Expression::Doubt => Some(DrawableEffectMark::Sweat(DrawableSweatMark::new(&self.sweat_geometry, offset, foreground_color))),
-            Expression::Angry => Some(DrawableEffectMark::Anger(DrawableAngerMark::new(&self.anger_geometry, offset, foreground_color))),
-            Expression::Happy => Some(DrawableEffectMark::Heart(DrawableHeartMark::new(&self.heart_geometry, offset, foreground_color))),
-            Expression::Sad => Some(DrawableEffectMark::Chill(DrawableChillMark::new(&self.chill_geometry, offset, foreground_color))),
-            Expression::Sleepy => Some(
+        // The marks are sized by the expression shape, so that the mark of the previous expression shrinks while the
+        // mark of the next one grows during a transition.
+        let shape = context.expression_shape();
+        let sizes = [shape.sweat_mark, shape.anger_mark, shape.heart_mark, shape.chill_mark, shape.bubble_mark];
+        let geometry = |geometry: &EffectGeometry, size: f32| Some(geometry.transform(&transform).scale(size)).filter(|geometry| geometry.size > 0);
+        let marks = [
+            geometry(&self.sweat_geometry, sizes[0]).map(|geometry| DrawableEffectMark::Sweat(DrawableSweatMark::new(&geometry, offset, foreground_color).rotate(&pivot(&self.sweat_geometry)))),
+            geometry(&self.anger_geometry, sizes[1]).map(|geometry| DrawableEffectMark::Anger(DrawableAngerMark::new(&geometry, offset, foreground_color).rotate(&pivot(&self.anger_geometry)))),
+            geometry(&self.heart_geometry, sizes[2]).map(|geometry| DrawableEffectMark::Heart(DrawableHeartMark::new(&geometry, offset, foreground_color).rotate(&pivot(&self.heart_geometry)))),
+            geometry(&self.chill_geometry, sizes[3]).map(|geometry| DrawableEffectMark::Chill(DrawableChillMark::new(&geometry, offset, foreground_color).rotate(&pivot(&self.chill_geometry)))),
+            geometry(&self.bubble_geometries[0], sizes[4]).zip(geometry(&self.bubble_geometries[1], sizes[4])).map(|(geometry0, geometry1)| {
                 DrawableEffectMark::Bubbles((
-                    DrawableBubbleMark::new(&self.bubble_geometries[0], offset, foreground_color),
-                    DrawableBubbleMark::new(&self.bubble_geometries[1], offset, foreground_color),
-                ),
-            )),
-            _ => None,
+                    DrawableBubbleMark::new(&geometry0, offset, foreground_color).rotate(&pivot(&self.bubble_geometries[0])),
+                    DrawableBubbleMark::new(&geometry1, offset, foreground_color).rotate(&pivot(&self.bubble_geometries[1])),
+                ))
+            }),
+        ];
+        let bounding_box = if head.angle == 0.0 {
+            rectangle_union_all(&bounding_boxes).unwrap()
+        } else {
+            // Keep the bounding box of a rotated effect tight so that it does not cover the face.
+            let shown = bounding_boxes.iter().zip(&marks).filter(|(_, mark)| mark.is_some()).map(|(bounding_box, _)| *bounding_box);
+            shown.reduce(|a, b| rectangle_union(&a, &b))
+                .unwrap_or_else(|| Rectangle::new(transform.point(REFERENCE_BOUNDING_RECT.center()), Size::zero()))
         };
         Self::Drawable {
             background_color,
-            mark: drawable_effect,
+            marks,
             bounding_box,
         }
     }
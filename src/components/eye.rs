@@ -2,20 +2,35 @@ use core::marker::PhantomData;
 #[allow(unused)]
 use micromath::F32Ext as _;
 
-use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget};
+use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget, Dimensions};
 use embedded_graphics::Drawable as DrawableGraphics;
-use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Circle, Rectangle, Primitive, Triangle};
-use crate::sprite::Sprite;
-use crate::util::prepare_sprite_buffer;
-use crate::{BasicPaletteContext, ExpressionContext, Expression};
+use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, Primitive, StrokeAlignment, StyledDimensions, StyledDrawable};
+use crate::sprite::{SpriteDrawable, draw_buffered};
+use crate::util::rectangle_union;
+use crate::{BasicPaletteContext, ExpressionContext, Pivot};
 use crate::component::Component;
 use crate::palette::{Palette, BasicPaletteKey};
 
+use super::eye_shape::{EyeGeometry, EyeInput, EyePaint, EyePrimitive, EyeShape, RoundEye};
 use super::mouth::MouthContext;
 
+/// How an [`Eye`] is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EyeStyle {
+    /// The shape filled with the primary color, which moves with the gaze.
+    #[default]
+    Filled,
+    /// An outlined sclera with a pupil which moves within it along the gaze, and a catchlight on the pupil.
+    /// Shapes which have no pupil, e.g. [`HeartEye`](super::eye_shape::HeartEye), are drawn filled.
+    PupilAndSclera,
+}
+
 pub struct Eye<'a, Context: EyeContext<'a>> {
     radius: f32,
     is_left: bool,
+    scale: f32,
+    style: EyeStyle,
+    shape: &'a dyn EyeShape,
     context: PhantomData<&'a Context>,
 }
 
@@ -24,14 +39,79 @@ impl<'a, Context: EyeContext<'a>> Eye<'a, Context> {
         Self {
             radius,
             is_left,
-            context: PhantomData::default(),
+            scale: 1.0,
+            style: EyeStyle::Filled,
+            shape: &RoundEye,
+            context: PhantomData,
         }
     }
+    pub fn style(&self) -> EyeStyle {
+        self.style
+    }
+    pub fn set_style(&mut self, style: EyeStyle) {
+        self.style = style;
+    }
+    pub fn shape(&self) -> &'a dyn EyeShape {
+        self.shape
+    }
+    pub fn set_shape(&mut self, shape: &'a dyn EyeShape) {
+        self.shape = shape;
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+    pub fn bounding_box(&self, bounding_rect: Rectangle) -> Rectangle {
+        self.bounding_box_scaled(bounding_rect, self.scale)
+    }
+    /// Bounding box when the eye is drawn at `scale` instead of its own scale factor.
+    pub fn bounding_box_scaled(&self, bounding_rect: Rectangle, scale: f32) -> Rectangle {
+        let center = bounding_rect.center();
+        // Leave room for the breath and gaze offsets on each side.
+        let radius = self.shape.extent(self.radius * scale, self.style).ceil() as i32;
+        let margin = ((3.0 + 3.0) * scale).ceil() as i32;
+        Rectangle::new(
+            center - Point::new(radius + margin, radius + margin),
+            Size::new((radius + margin) as u32 * 2, (radius + margin) as u32 * 2),
+        )
+    }
 }
 
 pub trait EyeContext<'a>: BasicPaletteContext<'a> +  GazeContext + ExpressionContext + MouthContext<'a> {
     fn open_ratio(&self) -> f32;
     fn set_open_ratio(&mut self, value: f32);
+    /// State of the eye on the left (`is_left`) or right side of the screen on top of the state of both eyes.
+    fn eye_state(&self, is_left: bool) -> EyeState;
+    fn set_eye_state(&mut self, is_left: bool, state: EyeState);
+    /// Size of the pupils of `EyeStyle::PupilAndSclera` from 0.0, constricted, to 1.0, dilated.
+    fn pupil_size(&self) -> f32;
+    fn set_pupil_size(&mut self, value: f32);
+}
+
+/// State of a single eye, which is combined with the open ratio and gaze shared by both eyes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EyeState {
+    /// Multiplied with the shared open ratio, e.g. 0.0 to wink.
+    pub open_ratio: f32,
+    /// Added to the shared gaze, e.g. to make the eyes converge.
+    pub gaze_offset_horizontal: f32,
+    pub gaze_offset_vertical: f32,
+}
+
+impl EyeState {
+    pub const NEUTRAL: Self = Self {
+        open_ratio: 1.0,
+        gaze_offset_horizontal: 0.0,
+        gaze_offset_vertical: 0.0,
+    };
+}
+
+impl Default for EyeState {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
 }
 
 pub trait GazeContext {
@@ -42,16 +122,95 @@ pub trait GazeContext {
 }
 
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableEye<Color: PixelColor> {
     bounding_box: Rectangle,
+    foreground_color: Color,
     background_color: Color,
-    style: PrimitiveStyle<Color>,
-    mask_style: PrimitiveStyle<Color>,
-    open_eye_main: Option<Circle>,
-    open_eye_triangle: Option<Triangle>,
-    open_eye_happy_circle: Option<Circle>,
-    open_eye_half_mask: Option<Rectangle>,
-    close_eye: Option<Rectangle>,
+    sclera_color: Color,
+    pupil_color: Color,
+    geometry: EyeGeometry,
+}
+
+impl<Color: PixelColor> DrawableEye<Color> {
+    /// Rotates the primitives around `pivot` and shrinks the bounding box to the rotated primitives.
+    fn rotate(self, pivot: &Pivot) -> Self {
+        if pivot.is_identity() {
+            return self;
+        }
+        let rotated = Self {
+            geometry: self.geometry.rotate(pivot),
+            ..self
+        };
+        let bounding_box = rotated.geometry.parts()
+            .map(|(primitive, paint)| {
+                let style = rotated.style(paint);
+                match primitive {
+                    EyePrimitive::Circle(p) => p.styled_bounding_box(&style),
+                    EyePrimitive::Triangle(p) => p.styled_bounding_box(&style),
+                    EyePrimitive::Rectangle(p) => p.styled_bounding_box(&style),
+                    EyePrimitive::Line(p, width) => p.styled_bounding_box(&rotated.line_style(paint, *width)),
+                }
+            })
+            .reduce(|a, b| rectangle_union(&a, &b))
+            .unwrap_or(rotated.bounding_box);
+        Self {
+            bounding_box,
+            ..rotated
+        }
+    }
+    fn color(&self, paint: &EyePaint) -> Color {
+        match paint {
+            EyePaint::Foreground => self.foreground_color,
+            EyePaint::Background => self.background_color,
+            EyePaint::Sclera { .. } | EyePaint::Catchlight => self.sclera_color,
+            EyePaint::Pupil => self.pupil_color,
+        }
+    }
+    fn style(&self, paint: &EyePaint) -> PrimitiveStyle<Color> {
+        let color = self.color(paint);
+        match paint {
+            EyePaint::Foreground | EyePaint::Background => PrimitiveStyleBuilder::new()
+                .stroke_color(color)
+                .stroke_width(1)
+                .fill_color(color)
+                .build(),
+            EyePaint::Sclera { outline_width } => PrimitiveStyleBuilder::new()
+                .stroke_color(self.foreground_color)
+                .stroke_width(*outline_width)
+                .stroke_alignment(StrokeAlignment::Inside)
+                .fill_color(color)
+                .build(),
+            EyePaint::Pupil | EyePaint::Catchlight => PrimitiveStyle::with_fill(color),
+        }
+    }
+    fn line_style(&self, paint: &EyePaint, width: u32) -> PrimitiveStyle<Color> {
+        PrimitiveStyle::with_stroke(self.color(paint), width)
+    }
+}
+
+impl<Color: PixelColor> Dimensions for DrawableEye<Color> {
+    fn bounding_box(&self) -> Rectangle {
+        self.bounding_box
+    }
+}
+
+impl<Color: PixelColor> SpriteDrawable for DrawableEye<Color> {
+    type Color = Color;
+    fn background_color(&self) -> Self::Color {
+        self.background_color
+    }
+    fn draw_sprite<D: DrawTarget<Color = Self::Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        for (primitive, paint) in self.geometry.parts() {
+            match primitive {
+                EyePrimitive::Circle(p) => p.into_styled(self.style(paint)).draw(target)?,
+                EyePrimitive::Triangle(p) => p.into_styled(self.style(paint)).draw(target)?,
+                EyePrimitive::Rectangle(p) => p.draw_styled(&self.style(paint), target)?,
+                EyePrimitive::Line(p, width) => p.into_styled(self.line_style(paint, *width)).draw(target)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics for DrawableEye<Color> {
@@ -60,16 +219,7 @@ impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics f
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        let mut buffer = prepare_sprite_buffer::<Color>(self.bounding_box);
-        let mut sprite = Sprite::<Color>::new_unaligned(&mut buffer, self.bounding_box).unwrap();
-        sprite.clear(self.background_color).ok();
-        self.open_eye_main.map_or(Ok(()), |p| p.into_styled(self.style.clone()).draw(&mut sprite)).ok();
-        self.open_eye_triangle.map_or(Ok(()), |p| p.into_styled(self.mask_style.clone()).draw(&mut sprite)).ok();
-        self.open_eye_happy_circle.map_or(Ok(()), |p| p.into_styled(self.mask_style.clone()).draw(&mut sprite)).ok();
-        self.open_eye_half_mask.map_or(Ok(()), |p| p.into_styled(self.mask_style.clone()).draw(&mut sprite)).ok();
-        self.close_eye.map_or(Ok(()), |p| p.into_styled(self.style.clone()).draw(&mut sprite)).ok();
-        sprite.draw(target)?;
-        Ok(())
+        draw_buffered(self, target)
     }
 }
 
@@ -79,111 +229,36 @@ impl <'a, Context: EyeContext<'a>> Component<'a> for Eye<'a, Context>
     type Context = Context;
     type Drawable = DrawableEye<Context::Color>;
     fn render(&self, bounding_rect: Rectangle, context: &'a Self::Context) -> Self::Drawable {
-        let foreground_color = context.get_basic_palette().get_color(&BasicPaletteKey::Primary);
-        let background_color = context.get_basic_palette().get_color(&BasicPaletteKey::Background);
-        let open_ratio = EyeContext::open_ratio(context);
-        let breath_offset = context.breath();
-        let style = PrimitiveStyleBuilder::new()
-            .stroke_color(foreground_color)
-            .stroke_width(1)
-            .fill_color(foreground_color)
-            .build();
-        let mask_style = PrimitiveStyleBuilder::new()
-            .stroke_color(background_color)
-            .stroke_width(1)
-            .fill_color(background_color)
-            .build();
+        let palette = context.get_basic_palette();
+        let eye_state = context.eye_state(self.is_left);
+        let breath = context.breath();
+        let head = context.head();
+        let scale = self.scale * head.scale;
         let center = bounding_rect.center();
-        let x = center.x as f32 + breath_offset * 3.0;
-        let y = center.y as f32 + breath_offset * 3.0;
-        let bounding_box = Rectangle::new(
-            center - Point::new(self.radius.ceil() as i32 + 3 + 3, self.radius.ceil() as i32 + 3 + 3),
-            Size::new((self.radius * 2.0 + 12.0).ceil() as u32, (self.radius * 2.0 + 12.0).ceil() as u32),
-        );
-        let offset_x = context.horizontal() * 3.0;
-        let offset_y = context.vertical() * 3.0;
-        let expression = context.expression();
-        if open_ratio > 0.0 {
-            let body = Circle::new(Point::new((x + offset_x - self.radius) as i32, (y + offset_y - self.radius) as i32), (self.radius * 2.0) as u32);
-            match expression {
-                Expression::Angry | Expression::Sad => {
-                    let x0 = x + offset_x - self.radius;
-                    let y0 = y + offset_y - self.radius;
-                    let x1 = x0 + self.radius * 2.0;
-                    let y1 = y0;
-                    let x2 = if self.is_left ^ (expression == Expression::Angry) { x0 } else {  x1 };
-                    let y2 = y0 + self.radius;
-                    let triangle = Triangle::new(Point::new(x0 as i32, y0 as i32), Point::new(x1 as i32, y1 as i32), Point::new(x2 as i32, y2 as i32));
-                    Self::Drawable {
-                        bounding_box,
-                        background_color,
-                        style,
-                        mask_style,
-                        open_eye_main: Some(body),
-                        open_eye_triangle: Some(triangle),
-                        open_eye_happy_circle: None,
-                        open_eye_half_mask: None,
-                        close_eye: None,
-                    }
-                },
-                Expression::Happy | Expression::Sleepy => {
-                    let x0 = x + offset_x - self.radius;
-                    let y0 = y + offset_y - self.radius + if expression == Expression::Happy { self.radius } else { 0.0 };
-                    let w = self.radius * 2.0 + 4.0;
-                    let h = self.radius + 2.0;
-                    let open_eye_happy_circle = if expression == Expression::Happy {
-                        let radius = self.radius / 1.5;
-                        Some(Circle::new(Point::new((x + offset_x - radius).round() as i32, (y + offset_y - radius).round() as i32), (radius * 2.0).round() as u32))
-                    } else {
-                        None
-                    };
-                    let open_eye_half_mask = Some(Rectangle::new(
-                        Point::new(x0 as i32, y0 as i32),
-                        Size::new(w as u32, h as u32)
-                    ));
-                    Self::Drawable {
-                        bounding_box,
-                        background_color,
-                        style,
-                        mask_style,
-                        open_eye_main: Some(body),
-                        open_eye_triangle: None,
-                        open_eye_happy_circle,
-                        open_eye_half_mask,
-                        close_eye: None,
-                    }
-                },
-                _ => {
-                    Self::Drawable {
-                        bounding_box,
-                        background_color,
-                        style,
-                        mask_style,
-                        open_eye_main: Some(body),
-                        open_eye_triangle: None,
-                        open_eye_happy_circle: None,
-                        open_eye_half_mask: None,
-                        close_eye: None,
-                    }
-                }
-            }
-        } else {
-            let x1 = x - self.radius + offset_x;
-            let y1 = y - 2.0 + offset_y;
-            let w = self.radius * 2.0;
-            let h = 4.0f32;
-            let close_eye = Some(Rectangle::new(Point::new(x1 as i32, y1 as i32), Size::new(w as u32, h as u32)));
-            Self::Drawable {
-                bounding_box,
-                background_color,
-                style,
-                mask_style,
-                open_eye_main: None,
-                open_eye_triangle: None,
-                open_eye_happy_circle: None,
-                open_eye_half_mask: None,
-                close_eye,
-            }
-        }
+        let input = EyeInput {
+            x: center.x as f32 + breath * 3.0 * scale,
+            y: center.y as f32 + breath * 3.0 * scale,
+            radius: self.radius * scale,
+            scale,
+            gaze_horizontal: (context.horizontal() + eye_state.gaze_offset_horizontal).clamp(-1.0, 1.0),
+            gaze_vertical: (context.vertical() + eye_state.gaze_offset_vertical).clamp(-1.0, 1.0),
+            open_ratio: EyeContext::open_ratio(context) * eye_state.open_ratio,
+            breath,
+            is_left: self.is_left,
+            style: self.style,
+            pupil_size: context.pupil_size(),
+            expression: context.expression_shape(),
+        };
+        let mut geometry = EyeGeometry::new();
+        self.shape.build(&input, &mut geometry);
+        let drawable = Self::Drawable {
+            bounding_box: self.bounding_box_scaled(bounding_rect, scale),
+            foreground_color: palette.get_color(&BasicPaletteKey::Primary),
+            background_color: palette.get_color(&BasicPaletteKey::Background),
+            sclera_color: palette.get_color(&BasicPaletteKey::Sclera),
+            pupil_color: palette.get_color(&BasicPaletteKey::Pupil),
+            geometry,
+        };
+        drawable.rotate(&Pivot::new(center, head.angle))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,452 @@
+#[allow(unused)]
+use micromath::F32Ext as _;
+
+use embedded_graphics::prelude::{Dimensions, Point, Size};
+use embedded_graphics::primitives::{Circle, Line, Rectangle, Triangle};
+
+use crate::util::{make_circle_center_radius, make_point_f32_rounded};
+use crate::{ExpressionShape, Pivot, RotatedRectangle};
+
+use super::eye::EyeStyle;
+
+/// Inputs of an [`EyeShape`] for one eye, resolved from the context and scaled to the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EyeInput {
+    /// Center of the eye with the breath offset applied.
+    pub x: f32,
+    pub y: f32,
+    /// Radius of the eye, including the scale.
+    pub radius: f32,
+    pub scale: f32,
+    /// Gaze from -1.0 to 1.0, including the offset of this eye.
+    pub gaze_horizontal: f32,
+    pub gaze_vertical: f32,
+    /// Open ratio of this eye from 0.0, closed, to 1.0, open.
+    pub open_ratio: f32,
+    /// Breath from -1.0 to 1.0, which is already applied to `x` and `y`.
+    pub breath: f32,
+    /// Whether the eye is on the left side of the screen.
+    pub is_left: bool,
+    pub style: EyeStyle,
+    pub pupil_size: f32,
+    pub expression: ExpressionShape,
+}
+
+impl EyeInput {
+    /// Offset by which a whole eye moves along the gaze. Together with the breath it stays within the margin of the bounding box.
+    pub fn gaze_offset(&self) -> (f32, f32) {
+        (self.gaze_horizontal * 3.0 * self.scale, self.gaze_vertical * 3.0 * self.scale)
+    }
+    /// Height of the line drawn for a closed eye.
+    pub fn closed_height(&self) -> f32 {
+        (4.0 * self.scale).max(1.0)
+    }
+}
+
+/// Color a part of an eye is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EyePaint {
+    /// `BasicPaletteKey::Primary`.
+    Foreground,
+    /// `BasicPaletteKey::Background`, which masks the parts drawn before.
+    Background,
+    /// `BasicPaletteKey::Sclera` outlined with `BasicPaletteKey::Primary`.
+    Sclera { outline_width: u32 },
+    /// `BasicPaletteKey::Pupil`.
+    Pupil,
+    /// `BasicPaletteKey::Sclera` without an outline.
+    Catchlight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EyePrimitive {
+    Circle(Circle),
+    Triangle(Triangle),
+    Rectangle(RotatedRectangle),
+    /// Line stroked with the given width.
+    Line(Line, u32),
+}
+
+impl EyePrimitive {
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        match self {
+            Self::Circle(circle) => Self::Circle(pivot.circle(*circle)),
+            Self::Triangle(triangle) => Self::Triangle(pivot.triangle(*triangle)),
+            Self::Rectangle(rectangle) => Self::Rectangle(pivot.rotated_rectangle(*rectangle)),
+            Self::Line(line, width) => Self::Line(Line::new(pivot.point(line.start), pivot.point(line.end)), *width),
+        }
+    }
+}
+
+/// Primitives of an eye in drawing order, kept in a fixed capacity so that no allocator is needed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EyeGeometry {
+    parts: [Option<(EyePrimitive, EyePaint)>; Self::CAPACITY],
+    len: usize,
+}
+
+impl EyeGeometry {
+    pub const CAPACITY: usize = 20;
+
+    pub fn new() -> Self {
+        Self {
+            parts: [None; Self::CAPACITY],
+            len: 0,
+        }
+    }
+    /// Adds a primitive which is drawn over the ones added before. Primitives beyond `CAPACITY` are ignored.
+    pub fn push(&mut self, primitive: EyePrimitive, paint: EyePaint) {
+        if self.len < Self::CAPACITY {
+            self.parts[self.len] = Some((primitive, paint));
+            self.len += 1;
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn parts(&self) -> impl Iterator<Item = &(EyePrimitive, EyePaint)> {
+        self.parts[..self.len].iter().flatten()
+    }
+    pub fn rotate(&self, pivot: &Pivot) -> Self {
+        let mut rotated = self.clone();
+        for (primitive, _) in rotated.parts.iter_mut().flatten() {
+            *primitive = primitive.rotate(pivot);
+        }
+        rotated
+    }
+    /// Masks which cover the top and bottom of `body` so that the visible band closes towards the middle of the eye.
+    /// The band never gets thinner than a closed eye so that the last frames of a blink blend into the closed line.
+    pub fn push_eyelids(&mut self, body: &Rectangle, input: &EyeInput) {
+        if input.open_ratio >= 1.0 {
+            return;
+        }
+        let height = body.size.height as f32;
+        let band = (height * input.open_ratio).max(input.closed_height());
+        let lid = ((height - band) / 2.0).round() as u32;
+        if lid == 0 {
+            return;
+        }
+        // Wider than the eye like the half mask of the happy eye.
+        let margin = (2.0 * input.scale).ceil() as i32;
+        let x = body.top_left.x - margin;
+        let width = body.size.width + margin as u32 * 2;
+        let upper = Rectangle::new(Point::new(x, body.top_left.y), Size::new(width, lid));
+        let lower = Rectangle::new(Point::new(x, body.top_left.y + (body.size.height - lid) as i32), Size::new(width, lid));
+        self.push(EyePrimitive::Rectangle(RotatedRectangle::Aligned(upper)), EyePaint::Background);
+        self.push(EyePrimitive::Rectangle(RotatedRectangle::Aligned(lower)), EyePaint::Background);
+    }
+    /// Horizontal line of a closed eye centered at `center_x`, `center_y`.
+    pub fn push_closed_line(&mut self, center_x: f32, center_y: f32, half_width: f32, input: &EyeInput) {
+        let x1 = center_x - half_width;
+        let h = input.closed_height();
+        let y1 = center_y - h / 2.0;
+        let w = half_width * 2.0;
+        let rectangle = Rectangle::new(Point::new(x1 as i32, y1 as i32), Size::new(w as u32, h as u32));
+        self.push(EyePrimitive::Rectangle(RotatedRectangle::Aligned(rectangle)), EyePaint::Foreground);
+    }
+    /// Masks of the expression for an eye of `radius_x` by `radius_y` around `center_x`, `center_y`:
+    /// angry and sad eyes are cut by a slanted triangle, happy eyes are masked from below and hollowed out, sleepy eyes are masked from above.
+    pub fn push_expression_masks(&mut self, center_x: f32, center_y: f32, radius_x: f32, radius_y: f32, input: &EyeInput) {
+        let shape = &input.expression;
+        let x0 = center_x - radius_x;
+        let y0 = center_y - radius_y;
+        if shape.slant != 0.0 {
+            let x1 = x0 + radius_x * 2.0;
+            let y1 = y0;
+            let x2 = if input.is_left ^ (shape.slant > 0.0) { x0 } else { x1 };
+            let y2 = y0 + radius_y * shape.slant.abs().min(1.0);
+            let triangle = Triangle::new(Point::new(x0 as i32, y0 as i32), Point::new(x1 as i32, y1 as i32), Point::new(x2 as i32, y2 as i32));
+            self.push(EyePrimitive::Triangle(triangle), EyePaint::Background);
+        }
+        let w = radius_x * 2.0 + 4.0 * input.scale;
+        let h = radius_y + 2.0 * input.scale;
+        if shape.eye_lower_mask > 0.0 {
+            let radius = radius_x.min(radius_y) / 1.5 * shape.eye_lower_mask.min(1.0);
+            let circle = Circle::new(Point::new((center_x - radius).round() as i32, (center_y - radius).round() as i32), (radius * 2.0).round() as u32);
+            self.push(EyePrimitive::Circle(circle), EyePaint::Background);
+        }
+        if shape.eye_upper_mask > 0.0 {
+            let mask_h = h * shape.eye_upper_mask.min(1.0);
+            let rectangle = Rectangle::new(Point::new(x0 as i32, y0 as i32), Size::new(w as u32, mask_h as u32));
+            self.push(EyePrimitive::Rectangle(RotatedRectangle::Aligned(rectangle)), EyePaint::Background);
+        }
+        if shape.eye_lower_mask > 0.0 {
+            let mask_h = h * shape.eye_lower_mask.min(1.0);
+            let rectangle = Rectangle::new(Point::new(x0 as i32, (y0 + radius_y + (h - mask_h)) as i32), Size::new(w as u32, mask_h as u32));
+            self.push(EyePrimitive::Rectangle(RotatedRectangle::Aligned(rectangle)), EyePaint::Background);
+        }
+    }
+    /// Connected lines through `points`, with a disk at each joint if `round_joints` so that the bends have no notches.
+    fn push_polyline(&mut self, points: impl IntoIterator<Item = Point>, width: u32, round_joints: bool) {
+        let mut previous: Option<Point> = None;
+        for point in points {
+            if let Some(previous) = previous {
+                if round_joints && self.len > 0 {
+                    self.push(EyePrimitive::Circle(Circle::with_center(previous, width)), EyePaint::Foreground);
+                }
+                self.push(EyePrimitive::Line(Line::new(previous, point), width), EyePaint::Foreground);
+            }
+            previous = Some(point);
+        }
+    }
+}
+
+impl Default for EyeGeometry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Geometry of an eye. [`Eye`](super::eye::Eye) resolves the gaze, open ratio and breath from the context into an
+/// [`EyeInput`] and draws the primitives the shape builds from it, rotated with the head.
+pub trait EyeShape {
+    /// Half of the width and height which the shape covers around its center for an eye of `radius`.
+    /// The bounding box adds room for the gaze and breath offsets of [`EyeInput::gaze_offset`] to this.
+    fn extent(&self, radius: f32, style: EyeStyle) -> f32 {
+        let _ = style;
+        radius
+    }
+    /// Adds the primitives of the eye to `geometry` in drawing order.
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry);
+}
+
+/// Width of the lines of the line shapes for an eye of `radius`.
+fn stroke_width(radius: f32) -> u32 {
+    (radius * 0.375).round().max(1.0) as u32
+}
+
+/// Round eyes, either filled or with a pupil and a sclera depending on [`EyeStyle`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoundEye;
+
+impl EyeShape for RoundEye {
+    fn extent(&self, radius: f32, style: EyeStyle) -> f32 {
+        match style {
+            EyeStyle::Filled => radius,
+            // The sclera is larger than a filled eye to leave room for the pupil.
+            EyeStyle::PupilAndSclera => radius * 1.75,
+        }
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let radius = self.extent(input.radius, input.style);
+        let (x, y) = (input.x, input.y);
+        // A filled eye moves as a whole, a sclera stays in place and only its pupil moves.
+        let (offset_x, offset_y) = match input.style {
+            EyeStyle::Filled => input.gaze_offset(),
+            EyeStyle::PupilAndSclera => (0.0, 0.0),
+        };
+        if input.open_ratio <= 0.0 {
+            geometry.push_closed_line(x + offset_x, y + offset_y, radius, input);
+            return;
+        }
+        let body = Circle::new(Point::new((x + offset_x - radius) as i32, (y + offset_y - radius) as i32), (radius * 2.0) as u32);
+        match input.style {
+            EyeStyle::Filled => geometry.push(EyePrimitive::Circle(body), EyePaint::Foreground),
+            EyeStyle::PupilAndSclera => {
+                let outline_width = (1.5 * input.scale).round().max(1.0) as u32;
+                geometry.push(EyePrimitive::Circle(body), EyePaint::Sclera { outline_width });
+                let pupil_radius = radius * (0.25 + 0.45 * input.pupil_size.clamp(0.0, 1.0));
+                let travel = (radius - pupil_radius - 2.0 * outline_width as f32).max(0.0);
+                let (pupil_x, pupil_y) = (x + input.gaze_horizontal * travel, y + input.gaze_vertical * travel);
+                let catchlight_radius = (pupil_radius * 0.35).max(1.0);
+                geometry.push(EyePrimitive::Circle(make_circle_center_radius(pupil_x, pupil_y, pupil_radius)), EyePaint::Pupil);
+                geometry.push(EyePrimitive::Circle(make_circle_center_radius(pupil_x - pupil_radius * 0.4, pupil_y - pupil_radius * 0.4, catchlight_radius)), EyePaint::Catchlight);
+            },
+        }
+        geometry.push_expression_masks(x + offset_x, y + offset_y, radius, radius, input);
+        geometry.push_eyelids(&body.bounding_box(), input);
+    }
+}
+
+/// Filled ellipses, tall by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EllipseEye {
+    /// Half width and half height relative to the radius of the eye.
+    pub width: f32,
+    pub height: f32,
+}
+
+impl EllipseEye {
+    pub const DEFAULT: Self = Self {
+        width: 0.75,
+        height: 1.3,
+    };
+    const SEGMENTS: usize = 12;
+}
+
+impl Default for EllipseEye {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl EyeShape for EllipseEye {
+    fn extent(&self, radius: f32, _style: EyeStyle) -> f32 {
+        radius * self.width.max(self.height)
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let (offset_x, offset_y) = input.gaze_offset();
+        let (x, y) = (input.x + offset_x, input.y + offset_y);
+        let (radius_x, radius_y) = (input.radius * self.width, input.radius * self.height);
+        if input.open_ratio <= 0.0 {
+            geometry.push_closed_line(x, y, radius_x, input);
+            return;
+        }
+        let center = make_point_f32_rounded(x, y);
+        let rim = |index: usize| {
+            let angle = index as f32 * 2.0 * core::f32::consts::PI / Self::SEGMENTS as f32;
+            make_point_f32_rounded(x + radius_x * angle.cos(), y + radius_y * angle.sin())
+        };
+        for index in 0..Self::SEGMENTS {
+            geometry.push(EyePrimitive::Triangle(Triangle::new(center, rim(index), rim(index + 1))), EyePaint::Foreground);
+        }
+        geometry.push_expression_masks(x, y, radius_x, radius_y, input);
+        let body = Rectangle::new(make_point_f32_rounded(x - radius_x, y - radius_y), Size::new((radius_x * 2.0).round() as u32, (radius_y * 2.0).round() as u32));
+        geometry.push_eyelids(&body, input);
+    }
+}
+
+/// Closed, smiling eyes drawn as arcs like "^ ^". The arcs flatten into lines as the eyes close.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArcEye;
+
+impl ArcEye {
+    const SEGMENTS: usize = 8;
+}
+
+impl EyeShape for ArcEye {
+    fn extent(&self, radius: f32, _style: EyeStyle) -> f32 {
+        radius * 1.25 + stroke_width(radius) as f32
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let (offset_x, offset_y) = input.gaze_offset();
+        let (x, y) = (input.x + offset_x, input.y + offset_y);
+        let half_width = input.radius * 1.25;
+        let height = input.radius * input.open_ratio.clamp(0.0, 1.0);
+        let points = (0..=Self::SEGMENTS).map(|index| {
+            let u = 2.0 * index as f32 / Self::SEGMENTS as f32 - 1.0;
+            make_point_f32_rounded(x + u * half_width, y + height / 2.0 - height * (1.0 - u * u))
+        });
+        geometry.push_polyline(points, stroke_width(input.radius), true);
+    }
+}
+
+/// Filled hearts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HeartEye;
+
+impl EyeShape for HeartEye {
+    fn extent(&self, radius: f32, _style: EyeStyle) -> f32 {
+        radius * 1.6
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let (offset_x, offset_y) = input.gaze_offset();
+        let (x, y) = (input.x + offset_x, input.y + offset_y);
+        let size = input.radius * 1.5;
+        if input.open_ratio <= 0.0 {
+            geometry.push_closed_line(x, y, size, input);
+            return;
+        }
+        // Two lobes on top of a triangle which points downwards.
+        let lobe_radius = size * 0.55;
+        let lobe_y = y - size * 0.3;
+        geometry.push(EyePrimitive::Circle(make_circle_center_radius(x - size * 0.5, lobe_y, lobe_radius)), EyePaint::Foreground);
+        geometry.push(EyePrimitive::Circle(make_circle_center_radius(x + size * 0.5, lobe_y, lobe_radius)), EyePaint::Foreground);
+        let triangle = Triangle::new(
+            make_point_f32_rounded(x - size * 1.02, y - size * 0.15),
+            make_point_f32_rounded(x + size * 1.02, y - size * 0.15),
+            make_point_f32_rounded(x, y + size * 0.95),
+        );
+        geometry.push(EyePrimitive::Triangle(triangle), EyePaint::Foreground);
+        let body = Rectangle::new(make_point_f32_rounded(x - size, y - size * 0.85), Size::new((size * 2.0).round() as u32, (size * 1.8).round() as u32));
+        geometry.push_eyelids(&body, input);
+    }
+}
+
+/// Filled five-pointed stars.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StarEye;
+
+impl EyeShape for StarEye {
+    fn extent(&self, radius: f32, _style: EyeStyle) -> f32 {
+        radius * 1.6
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let (offset_x, offset_y) = input.gaze_offset();
+        let (x, y) = (input.x + offset_x, input.y + offset_y);
+        let size = input.radius * 1.6;
+        if input.open_ratio <= 0.0 {
+            geometry.push_closed_line(x, y, size, input);
+            return;
+        }
+        // Outer vertices from the top in steps of a fifth of a turn, inner vertices halfway between them.
+        let vertex = |step: usize, radius: f32| {
+            let angle = (step as f32 * 0.2 - 0.5) * core::f32::consts::PI;
+            make_point_f32_rounded(x + radius * angle.cos(), y + radius * angle.sin())
+        };
+        let outer = |index: usize| vertex(index * 2, size);
+        let inner = |index: usize| vertex((index % 5) * 2 + 1, size * 0.4);
+        // Five spikes around a pentagon.
+        for index in 0..5 {
+            geometry.push(EyePrimitive::Triangle(Triangle::new(outer(index), inner(index), inner(index + 4))), EyePaint::Foreground);
+        }
+        for index in 1..4 {
+            geometry.push(EyePrimitive::Triangle(Triangle::new(inner(0), inner(index), inner(index + 1))), EyePaint::Foreground);
+        }
+        let body = Rectangle::new(make_point_f32_rounded(x - size, y - size), Size::new((size * 2.0).round() as u32, (size * 2.0).round() as u32));
+        geometry.push_eyelids(&body, input);
+    }
+}
+
+/// Dizzy spirals, which turn with the breath and squash vertically as the eyes close.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpiralEye;
+
+impl SpiralEye {
+    const SEGMENTS: usize = 18;
+    const TURNS: f32 = 2.25;
+}
+
+impl EyeShape for SpiralEye {
+    fn extent(&self, radius: f32, _style: EyeStyle) -> f32 {
+        radius * 1.5 + stroke_width(radius * 0.6) as f32
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let (offset_x, offset_y) = input.gaze_offset();
+        let (x, y) = (input.x + offset_x, input.y + offset_y);
+        let size = input.radius * 1.5;
+        let open_ratio = input.open_ratio.clamp(0.0, 1.0);
+        // Both spirals turn outwards in opposite directions so that the face stays symmetric.
+        let direction = if input.is_left { -1.0 } else { 1.0 };
+        let phase = input.breath * core::f32::consts::PI;
+        let points = (0..=Self::SEGMENTS).map(|index| {
+            let t = index as f32 / Self::SEGMENTS as f32;
+            let angle = direction * (t * Self::TURNS * 2.0 * core::f32::consts::PI + phase);
+            make_point_f32_rounded(x + size * t * angle.cos(), y + size * t * angle.sin() * open_ratio)
+        });
+        geometry.push_polyline(points, stroke_width(input.radius * 0.6), false);
+    }
+}
+
+/// Crosses like "x x", which flatten into lines as the eyes close.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CrossEye;
+
+impl EyeShape for CrossEye {
+    fn extent(&self, radius: f32, _style: EyeStyle) -> f32 {
+        radius * 1.2 + stroke_width(radius) as f32
+    }
+    fn build(&self, input: &EyeInput, geometry: &mut EyeGeometry) {
+        let (offset_x, offset_y) = input.gaze_offset();
+        let (x, y) = (input.x + offset_x, input.y + offset_y);
+        let size = input.radius * 1.2;
+        let height = size * input.open_ratio.clamp(0.0, 1.0);
+        let width = stroke_width(input.radius);
+        let line = |from_y: f32, to_y: f32| Line::new(make_point_f32_rounded(x - size, y + from_y), make_point_f32_rounded(x + size, y + to_y));
+        geometry.push(EyePrimitive::Line(line(-height, height), width), EyePaint::Foreground);
+        geometry.push(EyePrimitive::Line(line(height, -height), width), EyePaint::Foreground);
+    }
+}
@@ -1,11 +1,13 @@
 use core::marker::PhantomData;
+#[allow(unused)]
+use micromath::F32Ext as _;
 
-use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget};
+use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget, Dimensions};
 use embedded_graphics::Drawable as DrawableGraphics;
-use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Circle, Rectangle, Primitive, Triangle};
-use crate::sprite::Sprite;
-use crate::util::{make_point_f32_rounded, prepare_sprite_buffer};
-use crate::{BasicPaletteContext, ExpressionContext, Expression};
+use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, Primitive, StyledDimensions, StyledDrawable, Triangle};
+use crate::sprite::{SpriteDrawable, draw_buffered};
+use crate::util::{make_point_f32_rounded, rectangle_union};
+use crate::{BasicPaletteContext, ExpressionContext, Pivot, RotatedRectangle};
 use crate::component::Component;
 use crate::palette::{Palette, BasicPaletteKey};
 
@@ -15,6 +17,7 @@ pub struct Eyeblow<'a, Context: BasicPaletteContext<'a> + ExpressionContext> {
     width: u32,
     height: u32,
     is_left: bool,
+    scale: f32,
     context: PhantomData<&'a Context>,
 }
 
@@ -24,17 +27,88 @@ impl<'a, Context: BasicPaletteContext<'a> + ExpressionContext> Eyeblow<'a, Conte
             width,
             height,
             is_left,
-            context: PhantomData::default(),
+            scale: 1.0,
+            context: PhantomData,
         }
     }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+    fn scaled_size(&self, scale: f32) -> Size {
+        Size::new(
+            ((self.width as f32 * scale).round() as u32).max(1),
+            ((self.height as f32 * scale).round() as u32).max(1),
+        )
+    }
+    pub fn bounding_box(&self, bounding_rect: Rectangle) -> Rectangle {
+        self.bounding_box_scaled(bounding_rect, self.scale)
+    }
+    /// Bounding box when the eyeblow is drawn at `scale` instead of its own scale factor.
+    pub fn bounding_box_scaled(&self, bounding_rect: Rectangle, scale: f32) -> Rectangle {
+        let center = bounding_rect.center();
+        let size = self.scaled_size(scale);
+        let margin_x = ((3 + 3) as f32 * scale).ceil() as u32;
+        let margin_y = ((3 + 5) as f32 * scale).ceil() as u32;
+        Rectangle::new(
+            center - Point::new((size.width / 2 + margin_x) as i32, (size.height / 2 + margin_y) as i32),
+            Size::new(size.width + margin_x * 2, size.height + margin_y * 2),
+        )
+    }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableEyeblow<Color: PixelColor> {
     bounding_box: Rectangle,
     background_color: Color,
     style: PrimitiveStyle<Color>,
     angry_sad_triangles: Option<(Triangle, Triangle)>,
-    other_rect: Option<Rectangle>,
+    other_rect: Option<RotatedRectangle>,
+}
+
+impl<Color: PixelColor> DrawableEyeblow<Color> {
+    /// Rotates the primitives around `pivot` and shrinks the bounding box to the rotated primitives.
+    fn rotate(self, pivot: &Pivot) -> Self {
+        if pivot.is_identity() {
+            return self;
+        }
+        let angry_sad_triangles = self.angry_sad_triangles.map(|(t1, t2)| (pivot.triangle(t1), pivot.triangle(t2)));
+        let other_rect = self.other_rect.map(|rectangle| pivot.rotated_rectangle(rectangle));
+        let bounding_box = match (angry_sad_triangles, other_rect) {
+            (Some((t1, t2)), _) => rectangle_union(&t1.styled_bounding_box(&self.style), &t2.styled_bounding_box(&self.style)),
+            (None, Some(rectangle)) => rectangle.styled_bounding_box(&self.style),
+            (None, None) => self.bounding_box,
+        };
+        Self {
+            bounding_box,
+            angry_sad_triangles,
+            other_rect,
+            ..self
+        }
+    }
+}
+
+impl<Color: PixelColor> Dimensions for DrawableEyeblow<Color> {
+    fn bounding_box(&self) -> Rectangle {
+        self.bounding_box
+    }
+}
+
+impl<Color: PixelColor> SpriteDrawable for DrawableEyeblow<Color> {
+    type Color = Color;
+    fn background_color(&self) -> Self::Color {
+        self.background_color
+    }
+    fn draw_sprite<D: DrawTarget<Color = Self::Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        self.angry_sad_triangles.map_or(Ok(()), |p| {
+            p.0.into_styled(self.style).draw(target)?;
+            p.1.into_styled(self.style).draw(target)
+        })?;
+        self.other_rect.map_or(Ok(()), |p| p.draw_styled(&self.style, target))?;
+        Ok(())
+    }
 }
 
 impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics for DrawableEyeblow<Color> {
@@ -43,17 +117,7 @@ impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics f
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        let mut buffer = prepare_sprite_buffer::<Color>(self.bounding_box);
-        let mut sprite = Sprite::<Color>::new_unaligned(&mut buffer, self.bounding_box).unwrap();
-        sprite.clear(self.background_color).ok();
-        self.angry_sad_triangles.map_or(Ok(()), |p| {
-            p.0.into_styled(self.style.clone()).draw(&mut sprite).ok();
-            p.1.into_styled(self.style.clone()).draw(&mut sprite).ok();
-            Ok(())
-        })?;
-        self.other_rect.map_or(Ok(()), |p| p.into_styled(self.style.clone()).draw(&mut sprite)).ok();
-        sprite.draw(target)?;
-        Ok(())
+        draw_buffered(self, target)
     }
 }
 
@@ -71,62 +135,60 @@ impl <'a, Context: BasicPaletteContext<'a> + ExpressionContext + MouthContext<'a
             .fill_color(foreground_color)
             .build();
         
-        let breath_offset = context.breath() * 3.0;
+        let head = context.head();
+        let scale = self.scale * head.scale;
+        let breath_offset = context.breath() * 3.0 * scale;
         let center = bounding_rect.center();
-
-        let bounding_box = Rectangle::new(
-            center - Point::new((self.width / 2) as i32 + 3 + 3, (self.height / 2) as i32 + 3 + 5),
-            Size::new(self.width + (3 + 3) * 2, self.height + (3 + 5) * 2, ),
-        );
+        let bounding_box = self.bounding_box_scaled(bounding_rect, scale);
+        let pivot = Pivot::new(center, head.angle);
 
         let x = center.x as f32 + breath_offset;
         let y = center.y as f32 + breath_offset;
-        let expression = context.expression();
-        let width = self.width as f32;
-        let height = self.height as f32;
-        match expression {
-            Expression::Angry | Expression::Sad => {
-                let aspect = if self.is_left ^ (expression == Expression::Sad) { -1.0 } else { 1.0 };
-                let dx = aspect * 3.0;
-                let dy = aspect * 5.0;
-                let x1 = x - width / 2.0;
-                let x2 = x1 - dx;
-                let x4 = x + width / 2.0;
-                let x3 = x4 + dx;
-                let y1 = y - height / 2.0 - dy;
-                let y2 = y + height / 2.0 - dy;
-                let y3 = y - height / 2.0 + dy;
-                let y4 = y + height / 2.0 + dy;
-                let triangle1 = Triangle::new(
-                    make_point_f32_rounded(x1, y1), 
-                    make_point_f32_rounded(x2, y2),
-                    make_point_f32_rounded(x3, y3),
-                );
-                let triangle2 = Triangle::new(
-                    make_point_f32_rounded(x2, y2), 
-                    make_point_f32_rounded(x3, y3),
-                    make_point_f32_rounded(x4, y4),
-                );
-                Self::Drawable {
-                    bounding_box,
-                    background_color,
-                    style,
-                    angry_sad_triangles: Some((triangle1, triangle2)),
-                    other_rect: None,
-                }
-            },
-            _ => {
-                let x1 = x - width / 2.0;
-                let y1 = if expression == Expression::Happy { y - height / 2.0 - 5.0 } else { y - height / 2.0 };
-                let rect = Rectangle::new(make_point_f32_rounded(x1, y1), Size::new(self.width, self.height));
-                Self::Drawable {
-                    bounding_box,
-                    background_color,
-                    style,
-                    angry_sad_triangles: None,
-                    other_rect: Some(rect),
-                }
-            },
+        let shape = context.expression_shape();
+        let size = self.scaled_size(scale);
+        let width = size.width as f32;
+        let height = size.height as f32;
+        let lift = shape.eyebrow_lift * 5.0 * scale;
+        if shape.slant != 0.0 {
+            let aspect = if self.is_left { -shape.slant } else { shape.slant };
+            let dx = aspect * 3.0 * scale;
+            let dy = aspect * 5.0 * scale;
+            let x1 = x - width / 2.0;
+            let x2 = x1 - dx;
+            let x4 = x + width / 2.0;
+            let x3 = x4 + dx;
+            let y1 = y - height / 2.0 - dy - lift;
+            let y2 = y + height / 2.0 - dy - lift;
+            let y3 = y - height / 2.0 + dy - lift;
+            let y4 = y + height / 2.0 + dy - lift;
+            let triangle1 = Triangle::new(
+                make_point_f32_rounded(x1, y1), 
+                make_point_f32_rounded(x2, y2),
+                make_point_f32_rounded(x3, y3),
+            );
+            let triangle2 = Triangle::new(
+                make_point_f32_rounded(x2, y2), 
+                make_point_f32_rounded(x3, y3),
+                make_point_f32_rounded(x4, y4),
+            );
+            Self::Drawable {
+                bounding_box,
+                background_color,
+                style,
+                angry_sad_triangles: Some((triangle1, triangle2)),
+                other_rect: None,
+            }.rotate(&pivot)
+        } else {
+            let x1 = x - width / 2.0;
+            let y1 = y - height / 2.0 - lift;
+            let rect = RotatedRectangle::Aligned(Rectangle::new(make_point_f32_rounded(x1, y1), size));
+            Self::Drawable {
+                bounding_box,
+                background_color,
+                style,
+                angry_sad_triangles: None,
+                other_rect: Some(rect),
+            }.rotate(&pivot)
         }
     }
 }
\ No newline at end of file
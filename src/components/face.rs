@@ -1,11 +1,16 @@
 use core::str::FromStr;
 
-use embedded_graphics::prelude::{PixelColor, Size, Point, Drawable as DrawableGraphics, IntoStorage};
+use embedded_graphics::prelude::{PixelColor, Size, Point, Drawable as DrawableGraphics, DrawTarget, Dimensions};
 use embedded_graphics::primitives::Rectangle;
 use rand_core::SeedableRng;
 
-use crate::{Expression, ArrayPalette, BasicPaletteKey, BasicPaletteContext, ExpressionContext, Component, Palette};
-use crate::components::eye::{Eye, EyeContext, GazeContext};
+use crate::damage::{DamageList, draw_damaged, erase_uncovered};
+use crate::sprite::SpriteDrawable;
+use crate::orientation::OrientationTransform;
+use crate::util::{rectangle_union_all, scratch_size_for};
+use crate::{AnimationEvent, EventContext, EventQueue, Expression, ExpressionShape, MouthShape, Viseme, ArrayPalette, BasicPaletteKey, BasicPaletteContext, ExpressionContext, Component, REFERENCE_BOUNDING_RECT, LayoutTransform, HeadTransform, Pivot};
+use crate::components::eye::{Eye, EyeContext, EyeState, EyeStyle, GazeContext};
+use crate::components::eye_shape::EyeShape;
 use crate::components::mouth::{Mouth, MouthContext};
 
 use super::balloon::BalloonContext;
@@ -18,34 +23,80 @@ pub trait RandomGeneratorContext {
     fn rng(&mut self) -> &mut Self::Rng;
 }
 
+/// Transform of the whole head. Contexts which do not store one draw the head untransformed.
+pub trait HeadContext {
+    fn head(&self) -> HeadTransform {
+        HeadTransform::IDENTITY
+    }
+    fn set_head(&mut self, _value: HeadTransform) {}
+}
+
 pub struct DrawContext<Color: PixelColor, String> {
     pub expression: Expression,
+    /// Geometry which overrides the shape of `expression` during transitions.
+    pub expression_shape: Option<ExpressionShape>,
     pub breath: f32,
     pub gaze_horizontal: f32,
     pub gaze_vertical: f32,
     pub eye_open_ratio: f32,
+    /// Eyes drawn on the left and right side of the screen.
+    pub left_eye: EyeState,
+    pub right_eye: EyeState,
+    pub pupil_size: f32,
     pub mouth_open_ratio: f32,
+    pub viseme: Option<Viseme>,
+    /// Geometry which overrides the shape of `viseme` during transitions.
+    pub mouth_shape: Option<MouthShape>,
+    pub head: HeadTransform,
     pub palette: ArrayPalette<BasicPaletteKey, Color, {BasicPaletteKey::VARIANT_COUNT}>,
     pub rng: rand_xorshift::XorShiftRng,
     pub text: Option<String>,
+    /// Events which happened since the application last polled them.
+    pub events: EventQueue,
 }
 
 impl<Color: PixelColor + Default, String> Default for DrawContext<Color, String> {
     fn default() -> Self {
         Self {
             expression: Expression::Neutral,
+            expression_shape: None,
             breath: 0.0,
             gaze_horizontal: 0.0,
             gaze_vertical: 0.0,
             eye_open_ratio: 1.0,
+            left_eye: EyeState::NEUTRAL,
+            right_eye: EyeState::NEUTRAL,
+            pupil_size: 0.5,
             mouth_open_ratio: 0.0,
+            viseme: None,
+            mouth_shape: None,
+            head: HeadTransform::default(),
             palette: ArrayPalette::default(),
             rng: rand_xorshift::XorShiftRng::from_seed([0u8; 16]),
             text: None,
+            events: EventQueue::new(),
         }
     }
 }
 
+impl<Color: PixelColor + Default, String> DrawContext<Color, String> {
+    /// Default context whose random generator is seeded with `seed`, e.g. derived from a chip ID, so that each device
+    /// blinks and glances in its own pattern.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rng: rand_xorshift::XorShiftRng::seed_from_u64(seed),
+            ..Self::default()
+        }
+    }
+}
+
+impl<Color: PixelColor, String> DrawContext<Color, String> {
+    /// Restarts the random generator from `seed`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = rand_xorshift::XorShiftRng::seed_from_u64(seed);
+    }
+}
+
 impl<Color: PixelColor, String> RandomGeneratorContext for DrawContext<Color, String> {
     type Rng = rand_xorshift::XorShiftRng;
     fn rng(&mut self) -> &mut rand_xorshift::XorShiftRng {
@@ -65,6 +116,27 @@ impl<Color: PixelColor, String> ExpressionContext for DrawContext<Color, String>
     fn expression(&self) -> Expression {
         self.expression
     }
+    fn expression_shape(&self) -> ExpressionShape {
+        self.expression_shape.unwrap_or(ExpressionShape::of(self.expression))
+    }
+    fn set_expression_shape(&mut self, shape: Option<ExpressionShape>) {
+        self.expression_shape = shape;
+    }
+}
+
+impl<Color: PixelColor, String> EventContext for DrawContext<Color, String> {
+    fn push_event(&mut self, event: AnimationEvent) {
+        self.events.push(event);
+    }
+}
+
+impl<Color: PixelColor, String> HeadContext for DrawContext<Color, String> {
+    fn head(&self) -> HeadTransform {
+        self.head
+    }
+    fn set_head(&mut self, value: HeadTransform) {
+        self.head = value;
+    }
 }
 
 impl<Color: PixelColor, String> GazeContext for DrawContext<Color, String> {
@@ -89,6 +161,22 @@ impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw> + 'a, String> E
     fn set_open_ratio(&mut self, value: f32) {
         self.eye_open_ratio = value;
     }
+    fn eye_state(&self, is_left: bool) -> EyeState {
+        if is_left { self.left_eye } else { self.right_eye }
+    }
+    fn set_eye_state(&mut self, is_left: bool, state: EyeState) {
+        if is_left {
+            self.left_eye = state;
+        } else {
+            self.right_eye = state;
+        }
+    }
+    fn pupil_size(&self) -> f32 {
+        self.pupil_size
+    }
+    fn set_pupil_size(&mut self, value: f32) {
+        self.pupil_size = value;
+    }
 }
 
 impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw> + 'a, String> MouthContext<'a> for DrawContext<Color, String> {
@@ -98,6 +186,18 @@ impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw> + 'a, String> M
     fn set_open_ratio(&mut self, value: f32) {
         self.mouth_open_ratio = value;
     }
+    fn viseme(&self) -> Option<Viseme> {
+        self.viseme
+    }
+    fn set_viseme(&mut self, viseme: Option<Viseme>) {
+        self.viseme = viseme;
+    }
+    fn mouth_shape(&self) -> Option<MouthShape> {
+        self.mouth_shape.or(self.viseme.map(MouthShape::of))
+    }
+    fn set_mouth_shape(&mut self, shape: Option<MouthShape>) {
+        self.mouth_shape = shape;
+    }
     fn breath(&self) -> f32 {
         self.breath
     }
@@ -113,7 +213,7 @@ impl<'a, Color: PixelColor + From<Color::Raw> + Into<Color::Raw> + 'a, String: A
         self.text.as_ref().map(|string| string.as_ref())
     }
     fn set_text(&mut self, string: Option<&str>) {
-        self.text = string.map(|s| String::from_str(s).ok()).flatten();
+        self.text = string.and_then(|s| String::from_str(s).ok());
     }
 }
 
@@ -131,9 +231,11 @@ pub struct Face<'a, Context: FaceContext<'a>> {
     pos_eyeblow_l: Rectangle,
     pos_eyeblow_r: Rectangle,
     bounding_rect: Rectangle,
+    scale: f32,
 }
 
 impl<'a, Context: FaceContext<'a>> Face<'a, Context> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         eye_l: Eye<'a, Context>,
         eye_r: Eye<'a, Context>,
@@ -159,8 +261,52 @@ impl<'a, Context: FaceContext<'a>> Face<'a, Context> {
             pos_eyeblow_l,
             pos_eyeblow_r,
             bounding_rect,
+            scale: 1.0,
         }
     }
+    pub fn bounding_rect(&self) -> Rectangle {
+        self.bounding_rect
+    }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    /// Sets the scale factor of the face and all of its parts.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+        self.eye_l.set_scale(scale);
+        self.eye_r.set_scale(scale);
+        self.mouth.set_scale(scale);
+        self.eyeblow_l.set_scale(scale);
+        self.eyeblow_r.set_scale(scale);
+    }
+    pub fn eye_style(&self) -> EyeStyle {
+        self.eye_l.style()
+    }
+    pub fn set_eye_style(&mut self, style: EyeStyle) {
+        self.eye_l.set_style(style);
+        self.eye_r.set_style(style);
+    }
+    /// Draws both eyes with `shape`.
+    pub fn set_eye_shape(&mut self, shape: &'a dyn EyeShape) {
+        self.set_eye_shapes(shape, shape);
+    }
+    /// Draws the eye on the left side of the screen with `left` and the other one with `right`.
+    pub fn set_eye_shapes(&mut self, left: &'a dyn EyeShape, right: &'a dyn EyeShape) {
+        self.eye_r.set_shape(left);
+        self.eye_l.set_shape(right);
+    }
+    /// Size of the scratch buffer required to render this face with `head`, which is the size of the largest part.
+    pub fn scratch_size(&self, _bounding_rect: Rectangle, head: &HeadTransform) -> usize {
+        let scale = self.scale * head.scale;
+        let rotated = |bounding_box: Rectangle| Pivot::new(bounding_box.center(), head.angle).bounding_box(&bounding_box.offset(1));
+        scratch_size_for::<Context::Color>(&[
+            rotated(self.eye_l.bounding_box_scaled(self.pos_eye_l, scale)),
+            rotated(self.eye_r.bounding_box_scaled(self.pos_eye_r, scale)),
+            rotated(self.mouth.bounding_box_scaled(self.pos_mouth, scale)),
+            rotated(self.eyeblow_l.bounding_box_scaled(self.pos_eyeblow_l, scale)),
+            rotated(self.eyeblow_r.bounding_box_scaled(self.pos_eyeblow_r, scale)),
+        ])
+    }
 }
 
 impl<'a, Context: FaceContext<'a>> Default for Face<'a, Context> {
@@ -176,11 +322,13 @@ impl<'a, Context: FaceContext<'a>> Default for Face<'a, Context> {
             pos_mouth: Rectangle::new(Point::new(163, 148), Size::zero()),
             pos_eyeblow_l: Rectangle::new(Point::new(96, 67), Size::zero()),
             pos_eyeblow_r: Rectangle::new(Point::new(230, 72), Size::zero()),
-            bounding_rect: Rectangle::new(Point::new(0, 0), Size::new(320, 240)),
+            bounding_rect: REFERENCE_BOUNDING_RECT,
+            scale: 1.0,
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableFace<Color: PixelColor> {
     eye_l: DrawableEye<Color>,
     eye_r: DrawableEye<Color>,
@@ -189,6 +337,40 @@ pub struct DrawableFace<Color: PixelColor> {
     eyeblow_r: DrawableEyeblow<Color>,
 }
 
+impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableFace<Color> {
+    /// Erases the regions which the parts covered in `previous` but no longer cover, e.g. after the head moved.
+    ///
+    /// Call this for all components before drawing any of them, so that no part is erased after it was drawn.
+    pub fn erase_uncovered<D: DrawTarget<Color = Color>>(&self, previous: &Self, target: &mut D, damage: &mut DamageList, transform: &OrientationTransform) -> Result<(), D::Error> {
+        let background_color = self.eye_l.background_color();
+        erase_uncovered(&self.eye_l, &previous.eye_l, target, damage, background_color, transform)?;
+        erase_uncovered(&self.eye_r, &previous.eye_r, target, damage, background_color, transform)?;
+        erase_uncovered(&self.mouth, &previous.mouth, target, damage, background_color, transform)?;
+        erase_uncovered(&self.eyeblow_l, &previous.eyeblow_l, target, damage, background_color, transform)?;
+        erase_uncovered(&self.eyeblow_r, &previous.eyeblow_r, target, damage, background_color, transform)
+    }
+    /// Draws only the parts which differ from `previous` and records the updated regions in `damage`.
+    pub fn draw_damaged<D: DrawTarget<Color = Color>>(&self, previous: Option<&Self>, target: &mut D, damage: &mut DamageList, scratch: &mut [u8], transform: &OrientationTransform) -> Result<(), D::Error> {
+        draw_damaged(&self.eye_l, previous.map(|p| &p.eye_l), target, damage, scratch, transform)?;
+        draw_damaged(&self.eye_r, previous.map(|p| &p.eye_r), target, damage, scratch, transform)?;
+        draw_damaged(&self.mouth, previous.map(|p| &p.mouth), target, damage, scratch, transform)?;
+        draw_damaged(&self.eyeblow_l, previous.map(|p| &p.eyeblow_l), target, damage, scratch, transform)?;
+        draw_damaged(&self.eyeblow_r, previous.map(|p| &p.eyeblow_r), target, damage, scratch, transform)?;
+        Ok(())
+    }
+}
+
+impl<Color: PixelColor> Dimensions for DrawableFace<Color> {
+    fn bounding_box(&self) -> Rectangle {
+        rectangle_union_all(&[
+            self.eye_l.bounding_box(),
+            self.eye_r.bounding_box(),
+            self.mouth.bounding_box(),
+            self.eyeblow_l.bounding_box(),
+            self.eyeblow_r.bounding_box(),
+        ]).unwrap()
+    }
+}
 
 impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics for DrawableFace<Color> {
     type Color = Color;
@@ -210,23 +392,24 @@ impl <'a, Context: FaceContext<'a>> Component<'a> for Face<'a, Context> {
     type Context = Context;
     type Drawable = DrawableFace<<Context as BasicPaletteContext<'a>>::Color>;
     fn render(&self, bounding_rect: Rectangle, context: &'a Self::Context) -> Self::Drawable {
+        let transform = LayoutTransform::new(&self.bounding_rect, &bounding_rect, self.scale).with_head(&context.head());
+        let position = |pos: &Rectangle| Rectangle::new(transform.point(pos.top_left), pos.size);
         let mouth = {
-            self.mouth.render(self.pos_mouth, context)
+            self.mouth.render(position(&self.pos_mouth), context)
         };
         let eye_l = {
-            self.eye_l.render(self.pos_eye_l, context)
+            self.eye_l.render(position(&self.pos_eye_l), context)
         };
         let eye_r = {
-            self.eye_r.render(self.pos_eye_r, context)
+            self.eye_r.render(position(&self.pos_eye_r), context)
         };
         let eyeblow_l = {
-            self.eyeblow_l.render(self.pos_eyeblow_l, context)
+            self.eyeblow_l.render(position(&self.pos_eyeblow_l), context)
         };
         let eyeblow_r = {
-            self.eyeblow_r.render(self.pos_eyeblow_r, context)
+            self.eyeblow_r.render(position(&self.pos_eyeblow_r), context)
         };
         
-        // TODO: support scaling
         Self::Drawable {
             eye_l,
             eye_r,
@@ -235,4 +418,42 @@ impl <'a, Context: FaceContext<'a>> Component<'a> for Face<'a, Context> {
             eyeblow_r,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    use super::*;
+    use crate::Lerp;
+
+    #[test]
+    fn every_expression_fits_in_the_scratch_size() {
+        let expressions = [Expression::Angry, Expression::Sad, Expression::Doubt, Expression::Happy, Expression::Sleepy, Expression::Neutral];
+        // The expressions themselves and the halfway points of the transitions between them.
+        let shapes = expressions.iter().flat_map(|from| expressions.iter().map(move |to| ExpressionShape::of(*from).lerp(&ExpressionShape::of(*to), 0.5)));
+        let mouth_shapes = Viseme::ALL.iter().map(|viseme| Some(MouthShape::of(*viseme))).chain([None]);
+        for (shape, mouth_shape) in shapes.flat_map(|shape| mouth_shapes.clone().map(move |mouth_shape| (shape, mouth_shape))) {
+            for (breath, open_ratio, angle) in [(1.0, 1.0, 0.0), (-1.0, 1.0, 0.0), (1.0, 0.0, 0.0), (-1.0, 1.0, 0.3), (1.0, 1.0, -0.3)] {
+                let context = DrawContext::<BinaryColor, &str> {
+                    expression_shape: Some(shape),
+                    mouth_shape,
+                    breath,
+                    mouth_open_ratio: open_ratio,
+                    head: HeadTransform { angle, ..HeadTransform::IDENTITY },
+                    ..Default::default()
+                };
+                let face = Face::default();
+                let scratch_size = face.scratch_size(REFERENCE_BOUNDING_RECT, &context.head);
+                let drawable = face.render(REFERENCE_BOUNDING_RECT, &context);
+                let parts = [drawable.eye_l.bounding_box(), drawable.eye_r.bounding_box(), drawable.mouth.bounding_box(), drawable.eyeblow_l.bounding_box(), drawable.eyeblow_r.bounding_box()];
+                for part in parts {
+                    assert!(scratch_size_for::<BinaryColor>(&[part]) <= scratch_size, "{part:?} does not fit for {shape:?}, {mouth_shape:?}, {breath}, {open_ratio}, {angle}");
+                }
+                if angle == 0.0 {
+                    // Without rotation the outline of the mouth stays within the box reserved for it.
+                    assert_eq!(drawable.mouth.bounding_box().size, face.mouth.bounding_box(Rectangle::zero()).size, "{shape:?}, {mouth_shape:?}, {breath}, {open_ratio}");
+                }
+            }
+        }
+    }
+}
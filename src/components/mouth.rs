@@ -1,12 +1,15 @@
 use core::marker::PhantomData;
+#[allow(unused)]
+use micromath::F32Ext as _;
 
-use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget};
+use embedded_graphics::prelude::{PixelColor, Point, Size, DrawTarget, Dimensions};
 use embedded_graphics::Drawable as DrawableGraphics;
-use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, Primitive, StyledDrawable};
-use crate::sprite::Sprite;
-use crate::util::prepare_sprite_buffer;
-use crate::{BasicPaletteContext, ExpressionContext};
+use embedded_graphics::primitives::{Circle, Triangle, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, StyledDimensions, StyledDrawable};
+use crate::sprite::{SpriteDrawable, draw_buffered};
+use crate::{BasicPaletteContext, ExpressionContext, MouthShape, Pivot, RotatedRectangle, Viseme};
 use crate::component::Component;
+use crate::util::{make_point_f32_rounded, rectangle_union};
+use super::face::HeadContext;
 use crate::palette::{Palette, BasicPaletteKey};
 
 pub struct Mouth<'a, Context: MouthContext<'a>> {
@@ -14,6 +17,7 @@ pub struct Mouth<'a, Context: MouthContext<'a>> {
     max_width: u32,
     min_height: u32,
     max_height: u32,
+    scale: f32,
     context: PhantomData<&'a Context>,
 }
 
@@ -24,22 +28,173 @@ impl<'a, Context: MouthContext<'a>> Mouth<'a, Context> {
             max_width,
             min_height,
             max_height,
-            context: PhantomData::default(),
+            scale: 1.0,
+            context: PhantomData,
         }
     }
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+    fn scaled(length: u32, scale: f32) -> u32 {
+        ((length as f32 * scale).round() as u32).max(1)
+    }
+    pub fn bounding_box(&self, bounding_rect: Rectangle) -> Rectangle {
+        self.bounding_box_scaled(bounding_rect, self.scale)
+    }
+    /// Bounding box when the mouth is drawn at `scale` instead of its own scale factor.
+    pub fn bounding_box_scaled(&self, bounding_rect: Rectangle, scale: f32) -> Rectangle {
+        let max_width = Self::scaled(self.max_width, scale);
+        let max_height = Self::scaled(self.max_height, scale);
+        let margin = (3.0 * scale).ceil() as u32;
+        // Bent, tilted and zigzagged mouths reach further up and down than straight ones.
+        let outline_margin = MouthOutline::max_displacement(max_width as f32, scale).ceil() as u32;
+        Rectangle::new(
+            bounding_rect.top_left - Point::new((max_width / 2 + margin) as i32, (max_height / 2 + margin + outline_margin) as i32),
+            Size::new(max_width + margin * 2, max_height + (margin + outline_margin) * 2),
+        )
+    }
 }
 
-pub trait MouthContext<'a>: BasicPaletteContext<'a> + ExpressionContext {
+pub trait MouthContext<'a>: BasicPaletteContext<'a> + ExpressionContext + HeadContext {
     fn open_ratio(&self) -> f32;
     fn set_open_ratio(&mut self, value: f32);
+    /// Viseme the mouth shows. `None` draws the mouth from the open ratio.
+    fn viseme(&self) -> Option<Viseme>;
+    fn set_viseme(&mut self, viseme: Option<Viseme>);
+    /// Geometry the mouth is drawn with, or `None` to draw it from the open ratio.
+    fn mouth_shape(&self) -> Option<MouthShape>;
+    /// Overrides the geometry of the viseme, e.g. during transitions.
+    fn set_mouth_shape(&mut self, shape: Option<MouthShape>);
     fn breath(&self) -> f32;
     fn set_breath(&mut self, value: f32);
 }
+
+/// Mouth which is bent, tilted or zigzagged, drawn as vertical strips between its upper and lower edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MouthOutline {
+    center_x: f32,
+    center_y: f32,
+    width: f32,
+    height: f32,
+    curve: f32,
+    /// Vertical offset of the right end against the center, in pixels.
+    skew: f32,
+    /// Amplitude of the zigzag in pixels.
+    jaggedness: f32,
+    roundness: f32,
+    min_thickness: f32,
+    pivot: Pivot,
+}
+
+impl MouthOutline {
+    const SEGMENTS: usize = 16;
+    /// Bend of the center line of a fully curved mouth, relative to its width.
+    const CURVE: f32 = 0.1;
+    /// Offset of the ends of a fully skewed mouth, relative to its width.
+    const SKEW: f32 = 0.12;
+    /// Amplitude of the zigzag of a fully jagged mouth at scale 1.0.
+    const JAGGEDNESS: f32 = 3.0;
+
+    /// Largest distance by which the edges of a mouth up to `width` wide move up or down against a straight mouth,
+    /// while the curve, skew and jaggedness of the expression are at most 1.0.
+    fn max_displacement(width: f32, scale: f32) -> f32 {
+        width * (Self::CURVE + Self::SKEW) + Self::JAGGEDNESS * scale
+    }
+
+    /// Upper and lower edge at the boundary `index` of the strips.
+    fn edges(&self, index: usize) -> (Point, Point) {
+        let u = 2.0 * index as f32 / Self::SEGMENTS as f32 - 1.0;
+        let x = self.center_x + u * self.width / 2.0;
+        let profile = 1.0 + ((1.0 - u * u).max(0.0).sqrt() - 1.0) * self.roundness;
+        let zigzag = [0.0, 1.0, 0.0, -1.0][index % 4];
+        // A smile keeps its upper edge on the bent line and opens downwards, a frown the other way round.
+        let center = self.center_y + self.curve * (self.width * Self::CURVE * (1.0 - u * u) - self.height / 2.0) + self.skew * u + self.jaggedness * zigzag;
+        let top = center - self.height / 2.0 * profile * (1.0 - self.curve);
+        let bottom = center + self.height / 2.0 * profile * (1.0 + self.curve);
+        let (top, bottom) = if bottom - top < self.min_thickness {
+            let middle = (top + bottom) / 2.0;
+            (middle - self.min_thickness / 2.0, middle + self.min_thickness / 2.0)
+        } else {
+            (top, bottom)
+        };
+        (self.pivot.point(make_point_f32_rounded(x, top)), self.pivot.point(make_point_f32_rounded(x, bottom)))
+    }
+    fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        (0..Self::SEGMENTS).flat_map(move |index| {
+            let ((top0, bottom0), (top1, bottom1)) = (self.edges(index), self.edges(index + 1));
+            [Triangle::new(top0, top1, bottom1), Triangle::new(top0, bottom1, bottom0)]
+        })
+    }
+    /// Same outline shrunk by `inset` on every side, or `None` if nothing is left.
+    fn inset(&self, inset: f32) -> Option<Self> {
+        (self.width > inset * 2.0 && self.height > inset * 2.0).then_some(Self {
+            width: self.width - inset * 2.0,
+            height: self.height - inset * 2.0,
+            min_thickness: 0.0,
+            ..*self
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum MouthGeometry {
+    Rectangle {
+        mouth_rect: RotatedRectangle,
+        /// Vertical band and corner circles of a mouth with rounded corners.
+        rounding: Option<(RotatedRectangle, [Circle; 4])>,
+    },
+    Outline {
+        outline: MouthOutline,
+        /// Inner outline drawn with the background color to hollow the mouth out.
+        hollow: Option<MouthOutline>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct DrawableMouth<Color: PixelColor> {
     bounding_box: Rectangle,
     background_color: Color,
     style: PrimitiveStyle<Color>,
-    mouth_rect: Rectangle,
+    geometry: MouthGeometry,
+}
+
+impl<Color: PixelColor> Dimensions for DrawableMouth<Color> {
+    fn bounding_box(&self) -> Rectangle {
+        self.bounding_box
+    }
+}
+
+impl<Color: PixelColor> SpriteDrawable for DrawableMouth<Color> {
+    type Color = Color;
+    fn background_color(&self) -> Self::Color {
+        self.background_color
+    }
+    fn draw_sprite<D: DrawTarget<Color = Self::Color>>(&self, target: &mut D) -> Result<(), D::Error> {
+        match &self.geometry {
+            MouthGeometry::Rectangle { mouth_rect, rounding } => {
+                mouth_rect.draw_styled(&self.style, target)?;
+                if let Some((vertical_rect, corners)) = rounding {
+                    vertical_rect.draw_styled(&self.style, target)?;
+                    for corner in corners {
+                        corner.draw_styled(&self.style, target)?;
+                    }
+                }
+            },
+            MouthGeometry::Outline { outline, hollow } => {
+                for triangle in outline.triangles() {
+                    triangle.draw_styled(&self.style, target)?;
+                }
+                let hollow_style = PrimitiveStyle::with_fill(self.background_color);
+                for triangle in hollow.iter().flat_map(|hollow| hollow.triangles()) {
+                    triangle.draw_styled(&hollow_style, target)?;
+                }
+            },
+        }
+        Ok(())
+    }
 }
 
 impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics for DrawableMouth<Color> {
@@ -48,12 +203,7 @@ impl<Color: PixelColor + Into<Color::Raw> + From<Color::Raw>> DrawableGraphics f
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: embedded_graphics::prelude::DrawTarget<Color = Self::Color> {
-        let mut buffer = prepare_sprite_buffer::<Color>(self.bounding_box);
-        let mut sprite = Sprite::<Color>::new_unaligned(&mut buffer, self.bounding_box).unwrap();
-        sprite.clear(self.background_color).ok();
-        self.mouth_rect.into_styled(self.style.clone()).draw(&mut sprite).ok();
-        sprite.draw(target)?;
-        Ok(())
+        draw_buffered(self, target)
     }
 }
 
@@ -72,20 +222,70 @@ impl <'a, Context: MouthContext<'a>> Component<'a> for Mouth<'a, Context>
             .stroke_width(1)
             .fill_color(foreground_color)
             .build();
-        let bounding_box = Rectangle::new(
-            bounding_rect.top_left - Point::new((self.max_width/2) as i32 + 3, (self.max_height / 2) as i32 + 3),
-            Size::new(self.max_width + 6, self.max_height + 6),
-        );
-        let h = self.min_height + (((self.max_height - self.min_height) as f32) * open_ratio) as u32;
-        let w = self.min_width + (((self.max_width - self.min_width) as f32) * (1.0 - open_ratio)) as u32;
+        let head = context.head();
+        let scale = self.scale * head.scale;
+        let (min_width, max_width) = (Self::scaled(self.min_width, scale), Self::scaled(self.max_width, scale));
+        let (min_height, max_height) = (Self::scaled(self.min_height, scale), Self::scaled(self.max_height, scale));
+        let mouth_shape = context.mouth_shape().unwrap_or(MouthShape::from_open_ratio(open_ratio));
+        let shape = context.expression_shape();
+        let h = min_height + (((max_height - min_height) as f32) * mouth_shape.height.max(shape.mouth_min_open).clamp(0.0, 1.0)) as u32;
+        let w = min_width + (((max_width - min_width) as f32) * mouth_shape.width.clamp(0.0, 1.0)) as u32;
+        let w = ((w as f32 * shape.mouth_width).round() as u32).max(1);
+        let h = ((h as f32 * shape.mouth_height).round() as u32).max(1);
         let x = bounding_rect.top_left.x - (w / 2) as i32;
-        let y = bounding_rect.top_left.y - (h / 2) as i32 + (breath * 2.0) as i32;
-        let mouth_rect = Rectangle::new(Point::new(x, y), Size::new(w, h));
+        let y = bounding_rect.top_left.y - (h / 2) as i32 + (breath * 2.0 * scale) as i32;
+        let pivot = Pivot::new(bounding_rect.top_left, head.angle);
+        let roundness = (mouth_shape.roundness + shape.mouth_roundness).clamp(0.0, 1.0);
+        let is_outline = [shape.mouth_curve, shape.mouth_skew, shape.mouth_jaggedness, shape.mouth_hollow].iter().any(|value| value.abs() >= 0.01);
+        if is_outline {
+            let outline = MouthOutline {
+                center_x: bounding_rect.top_left.x as f32,
+                center_y: (bounding_rect.top_left.y + (breath * 2.0 * scale) as i32) as f32,
+                width: w as f32,
+                height: h as f32,
+                curve: shape.mouth_curve.clamp(-1.0, 1.0),
+                skew: shape.mouth_skew * w as f32 * MouthOutline::SKEW,
+                jaggedness: shape.mouth_jaggedness * MouthOutline::JAGGEDNESS * scale,
+                roundness: roundness.max(shape.mouth_curve.abs()).min(1.0),
+                min_thickness: (2.0 * scale).max(1.0),
+                pivot,
+            };
+            let hollow = if shape.mouth_hollow >= 0.5 { outline.inset((3.0 * scale).max(2.0)) } else { None };
+            let bounding_box = outline.triangles().fold(self.bounding_box_scaled(bounding_rect, scale), |bounding_box, triangle| {
+                rectangle_union(&bounding_box, &triangle.styled_bounding_box(&style))
+            });
+            return Self::Drawable {
+                bounding_box,
+                background_color,
+                style,
+                geometry: MouthGeometry::Outline { outline, hollow },
+            };
+        }
+        let radius = (w.min(h) as f32 / 2.0 * roundness) as u32;
+        let (mouth_rect, rounding) = if radius == 0 {
+            (pivot.rectangle(Rectangle::new(Point::new(x, y), Size::new(w, h))), None)
+        } else {
+            let diameter = radius * 2;
+            let (right, bottom) = (x + (w - diameter) as i32, y + (h - diameter) as i32);
+            let corners = [Point::new(x, y), Point::new(right, y), Point::new(x, bottom), Point::new(right, bottom)]
+                .map(|top_left| pivot.circle(Circle::new(top_left, diameter)));
+            let vertical_rect = pivot.rectangle(Rectangle::new(Point::new(x + radius as i32, y), Size::new(w - diameter, h)));
+            (pivot.rectangle(Rectangle::new(Point::new(x, y + radius as i32), Size::new(w, h - diameter))), Some((vertical_rect, corners)))
+        };
+        let bounding_box = if pivot.is_identity() {
+            self.bounding_box_scaled(bounding_rect, scale)
+        } else {
+            rounding.iter().fold(mouth_rect.styled_bounding_box(&style), |bounding_box, (vertical_rect, corners)| {
+                corners.iter().fold(rectangle_union(&bounding_box, &vertical_rect.styled_bounding_box(&style)), |bounding_box, corner| {
+                    rectangle_union(&bounding_box, &corner.styled_bounding_box(&style))
+                })
+            })
+        };
         Self::Drawable {
             bounding_box,
             background_color,
             style,
-            mouth_rect,
+            geometry: MouthGeometry::Rectangle { mouth_rect, rounding },
         }
     }
 }
\ No newline at end of file
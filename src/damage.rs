@@ -0,0 +1,116 @@
+use embedded_graphics::{prelude::{Dimensions, DrawTarget, PixelColor}, primitives::Rectangle};
+
+use crate::sprite::{SpriteDrawable, draw_with_scratch_oriented};
+use crate::orientation::OrientationTransform;
+use crate::util::{rectangle_union, rectangle_difference};
+
+/// List of screen regions updated by a frame.
+///
+/// The list has a fixed capacity so that it does not require an allocator. When it is full, new regions are
+/// merged into the last entry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DamageList {
+    rectangles: [Rectangle; Self::CAPACITY],
+    len: usize,
+}
+
+impl DamageList {
+    pub const CAPACITY: usize = 8;
+
+    pub const fn new() -> Self {
+        Self {
+            rectangles: [Rectangle::zero(); Self::CAPACITY],
+            len: 0,
+        }
+    }
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn push(&mut self, rectangle: Rectangle) {
+        if rectangle.is_zero_sized() || self.contains(&rectangle) {
+            return;
+        }
+        if self.len < Self::CAPACITY {
+            self.rectangles[self.len] = rectangle;
+            self.len += 1;
+        } else {
+            let last = &mut self.rectangles[Self::CAPACITY - 1];
+            *last = rectangle_union(last, &rectangle);
+        }
+    }
+    /// Returns true if `rectangle` is entirely covered by one of the damaged regions.
+    pub fn contains(&self, rectangle: &Rectangle) -> bool {
+        self.as_slice().iter().any(|r| r.intersection(rectangle) == *rectangle)
+    }
+    pub fn intersects(&self, rectangle: &Rectangle) -> bool {
+        self.as_slice().iter().any(|r| !r.intersection(rectangle).is_zero_sized())
+    }
+    /// Restricts all regions to `area`, removing the ones outside of it.
+    pub fn clip(&mut self, area: &Rectangle) {
+        let mut len = 0;
+        for index in 0..self.len {
+            let clipped = self.rectangles[index].intersection(area);
+            if !clipped.is_zero_sized() {
+                self.rectangles[len] = clipped;
+                len += 1;
+            }
+        }
+        self.len = len;
+    }
+    /// Maps all regions from logical to display coordinates.
+    pub fn transform(&mut self, transform: &OrientationTransform) {
+        for rectangle in &mut self.rectangles[..self.len] {
+            *rectangle = transform.rectangle(rectangle);
+        }
+    }
+    pub fn as_slice(&self) -> &[Rectangle] {
+        &self.rectangles[..self.len]
+    }
+    pub fn bounding_box(&self) -> Option<Rectangle> {
+        crate::util::rectangle_union_all(self.as_slice())
+    }
+}
+
+impl Default for DamageList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws `current` if it differs from `previous` or overlaps a region damaged earlier in the frame, and records
+/// the updated region in `damage`. `scratch` is used as the sprite buffer.
+pub fn draw_damaged<T, D>(current: &T, previous: Option<&T>, target: &mut D, damage: &mut DamageList, scratch: &mut [u8], transform: &OrientationTransform) -> Result<(), D::Error>
+    where
+        T: SpriteDrawable + PartialEq,
+        T::Color: From<<T::Color as PixelColor>::Raw> + Into<<T::Color as PixelColor>::Raw>,
+        D: DrawTarget<Color = T::Color>,
+{
+    let bounding_box = current.bounding_box();
+    if previous != Some(current) || damage.intersects(&bounding_box) {
+        draw_with_scratch_oriented(current, target, scratch, transform)?;
+        damage.push(bounding_box);
+        if let Some(previous) = previous {
+            damage.push(previous.bounding_box());
+        }
+    }
+    Ok(())
+}
+
+/// Fills the parts of the bounding box of `previous` which are not covered by `current` with `background_color`, and
+/// records them in `damage` so that the parts below them are redrawn.
+pub fn erase_uncovered<T, D>(current: &T, previous: &T, target: &mut D, damage: &mut DamageList, background_color: D::Color, transform: &OrientationTransform) -> Result<(), D::Error>
+    where
+        T: Dimensions,
+        D: DrawTarget,
+{
+    for rectangle in rectangle_difference(&previous.bounding_box(), &current.bounding_box()) {
+        if !rectangle.is_zero_sized() {
+            target.fill_solid(&transform.rectangle(&rectangle), background_color)?;
+            damage.push(rectangle);
+        }
+    }
+    Ok(())
+}
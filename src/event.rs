@@ -0,0 +1,78 @@
+use crate::Expression;
+
+/// Something the face did, for syncing sounds or LEDs with the animation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimationEvent {
+    /// The eyelids started closing.
+    BlinkStarted,
+    /// The eyelids are fully open again, or the blink was cut short by disabling `Behavior::Blink`.
+    BlinkEnded,
+    /// A random saccade moved the gaze to the given position.
+    Saccade { horizontal: f32, vertical: f32 },
+    /// The face has fully morphed into the expression.
+    ExpressionTransitionFinished(Expression),
+    BalloonShown,
+    BalloonHidden,
+    /// A speech started with the speech API has finished, or was stopped by disabling `Behavior::Speech`.
+    SpeechFinished,
+}
+
+/// Fixed-size queue of events. When it is full, the oldest event is dropped.
+#[derive(Clone, Debug, Default)]
+pub struct EventQueue {
+    events: [Option<AnimationEvent>; Self::CAPACITY],
+    head: usize,
+    len: usize,
+    dropped: u32,
+}
+
+impl EventQueue {
+    pub const CAPACITY: usize = 16;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(&mut self, event: AnimationEvent) {
+        if self.len == Self::CAPACITY {
+            self.head = (self.head + 1) % Self::CAPACITY;
+            self.len -= 1;
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.events[(self.head + self.len) % Self::CAPACITY] = Some(event);
+        self.len += 1;
+    }
+    /// Removes and returns the oldest event.
+    pub fn pop(&mut self) -> Option<AnimationEvent> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head].take();
+        self.head = (self.head + 1) % Self::CAPACITY;
+        self.len -= 1;
+        event
+    }
+    /// Removes the events from the queue, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = AnimationEvent> + '_ {
+        core::iter::from_fn(|| self.pop())
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn clear(&mut self) {
+        *self = Self {
+            dropped: self.dropped,
+            ..Self::default()
+        };
+    }
+    /// Number of events dropped so far because the queue was full.
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+pub trait EventContext {
+    fn push_event(&mut self, event: AnimationEvent);
+}
@@ -1,5 +1,10 @@
+use num_enum::IntoPrimitive;
+use variant_count::VariantCount;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+use crate::Lerp;
+
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, IntoPrimitive, VariantCount)]
 pub enum Expression {
     Angry,
     Sad,
@@ -9,6 +14,116 @@ pub enum Expression {
     Neutral,
 }
 
+/// Continuous geometry of an expression, which can be blended between two expressions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExpressionShape {
+    /// Slant of the eyebrows and the eye masks, 1.0 for angry and -1.0 for sad.
+    pub slant: f32,
+    /// Raises the eyebrows, 1.0 for happy.
+    pub eyebrow_lift: f32,
+    /// Masks the upper half of the eyes, 1.0 for sleepy.
+    pub eye_upper_mask: f32,
+    /// Masks the lower half of the eyes and hollows them out, 1.0 for happy.
+    pub eye_lower_mask: f32,
+    /// Factors applied to the size of the mouth.
+    pub mouth_width: f32,
+    pub mouth_height: f32,
+    /// Bends the mouth, 1.0 for a smile which opens into a D shape and -1.0 for a frown.
+    pub mouth_curve: f32,
+    /// Tilts the mouth, 1.0 for doubt.
+    pub mouth_skew: f32,
+    /// Zigzags the mouth, 1.0 for angry.
+    pub mouth_jaggedness: f32,
+    /// Rounds the mouth in addition to the roundness of the viseme.
+    pub mouth_roundness: f32,
+    /// Hollows the mouth out into a ring, 1.0 for sleepy.
+    pub mouth_hollow: f32,
+    /// Open ratio below which the mouth does not close.
+    pub mouth_min_open: f32,
+    /// Sizes of the marks of the effect, 1.0 for the expression which shows the mark.
+    pub sweat_mark: f32,
+    pub anger_mark: f32,
+    pub heart_mark: f32,
+    pub chill_mark: f32,
+    pub bubble_mark: f32,
+}
+
+impl ExpressionShape {
+    pub const NEUTRAL: Self = Self {
+        slant: 0.0,
+        eyebrow_lift: 0.0,
+        eye_upper_mask: 0.0,
+        eye_lower_mask: 0.0,
+        mouth_width: 1.0,
+        mouth_height: 1.0,
+        mouth_curve: 0.0,
+        mouth_skew: 0.0,
+        mouth_jaggedness: 0.0,
+        mouth_roundness: 0.0,
+        mouth_hollow: 0.0,
+        mouth_min_open: 0.0,
+        sweat_mark: 0.0,
+        anger_mark: 0.0,
+        heart_mark: 0.0,
+        chill_mark: 0.0,
+        bubble_mark: 0.0,
+    };
+
+    pub const fn of(expression: Expression) -> Self {
+        match expression {
+            Expression::Angry => Self { slant: 1.0, mouth_width: 0.9, mouth_height: 0.6, mouth_jaggedness: 1.0, anger_mark: 1.0, ..Self::NEUTRAL },
+            Expression::Sad => Self { slant: -1.0, mouth_width: 0.8, mouth_curve: -1.0, chill_mark: 1.0, ..Self::NEUTRAL },
+            Expression::Doubt => Self { mouth_width: 0.7, mouth_skew: 1.0, sweat_mark: 1.0, ..Self::NEUTRAL },
+            Expression::Happy => Self { eyebrow_lift: 1.0, eye_lower_mask: 1.0, mouth_curve: 1.0, heart_mark: 1.0, ..Self::NEUTRAL },
+            Expression::Sleepy => Self { eye_upper_mask: 1.0, mouth_width: 0.4, mouth_roundness: 1.0, mouth_hollow: 1.0, mouth_min_open: 0.3, bubble_mark: 1.0, ..Self::NEUTRAL },
+            Expression::Neutral => Self::NEUTRAL,
+        }
+    }
+}
+
+impl Lerp for ExpressionShape {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            slant: self.slant.lerp(&other.slant, t),
+            eyebrow_lift: self.eyebrow_lift.lerp(&other.eyebrow_lift, t),
+            eye_upper_mask: self.eye_upper_mask.lerp(&other.eye_upper_mask, t),
+            eye_lower_mask: self.eye_lower_mask.lerp(&other.eye_lower_mask, t),
+            mouth_width: self.mouth_width.lerp(&other.mouth_width, t),
+            mouth_height: self.mouth_height.lerp(&other.mouth_height, t),
+            mouth_curve: self.mouth_curve.lerp(&other.mouth_curve, t),
+            mouth_skew: self.mouth_skew.lerp(&other.mouth_skew, t),
+            mouth_jaggedness: self.mouth_jaggedness.lerp(&other.mouth_jaggedness, t),
+            mouth_roundness: self.mouth_roundness.lerp(&other.mouth_roundness, t),
+            mouth_hollow: self.mouth_hollow.lerp(&other.mouth_hollow, t),
+            mouth_min_open: self.mouth_min_open.lerp(&other.mouth_min_open, t),
+            sweat_mark: self.sweat_mark.lerp(&other.sweat_mark, t),
+            anger_mark: self.anger_mark.lerp(&other.anger_mark, t),
+            heart_mark: self.heart_mark.lerp(&other.heart_mark, t),
+            chill_mark: self.chill_mark.lerp(&other.chill_mark, t),
+            bubble_mark: self.bubble_mark.lerp(&other.bubble_mark, t),
+        }
+    }
+}
+
+impl Default for ExpressionShape {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+impl From<Expression> for ExpressionShape {
+    fn from(expression: Expression) -> Self {
+        Self::of(expression)
+    }
+}
+
 pub trait ExpressionContext {
     fn expression(&self) -> Expression;
-}
\ No newline at end of file
+    /// Geometry the components are drawn with. Differs from the shape of `expression` while a transition is running.
+    fn expression_shape(&self) -> ExpressionShape {
+        ExpressionShape::of(self.expression())
+    }
+    /// Overrides the geometry of the expression. `None` draws the shape of `expression`.
+    /// Contexts which do not store an override ignore it and always draw the shape of `expression`.
+    fn set_expression_shape(&mut self, _shape: Option<ExpressionShape>) {}
+}
@@ -1,4 +1,5 @@
 #![no_std]
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
 mod palette;
@@ -7,7 +8,19 @@ mod expression;
 mod animation;
 mod avatar;
 mod util;
-mod sprite;
+pub mod sprite;
+mod time;
+mod damage;
+mod command;
+mod async_avatar;
+mod orientation;
+mod tween;
+mod combinator;
+mod event;
+mod replay;
+mod lipsync;
+mod viseme;
+mod speech;
 
 pub mod components;
 
@@ -15,4 +28,16 @@ pub use palette::*;
 pub use component::*;
 pub use expression::*;
 pub use animation::*;
-pub use avatar::*;
\ No newline at end of file
+pub use avatar::*;
+pub use time::*;
+pub use damage::*;
+pub use command::*;
+pub use async_avatar::*;
+pub use orientation::*;
+pub use tween::*;
+pub use combinator::*;
+pub use event::*;
+pub use replay::*;
+pub use lipsync::*;
+pub use viseme::*;
+pub use speech::*;
\ No newline at end of file
@@ -0,0 +1,197 @@
+#[allow(unused)]
+use micromath::F32Ext as _;
+
+/// How the level of a sample buffer is measured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Envelope {
+    /// Root mean square, follows the loudness of speech.
+    #[default]
+    Rms,
+    /// Largest absolute sample, reacts to short transients.
+    Peak,
+}
+
+/// Parameters of a [`LipSync`]. Levels are relative to the full scale of the samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LipSyncConfig {
+    pub sample_rate: u32,
+    pub envelope: Envelope,
+    /// Time constant with which the mouth opens.
+    pub attack_milliseconds: u32,
+    /// Time constant with which the mouth closes.
+    pub release_milliseconds: u32,
+    /// Levels below this keep the mouth closed.
+    pub noise_gate: f32,
+    /// Multiplies the level above the noise gate before it is clamped to 0.0..=1.0.
+    pub gain: f32,
+}
+
+impl LipSyncConfig {
+    pub const DEFAULT: Self = Self {
+        sample_rate: 16000,
+        envelope: Envelope::Rms,
+        attack_milliseconds: 30,
+        release_milliseconds: 120,
+        noise_gate: 0.02,
+        gain: 4.0,
+    };
+    pub const fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            ..Self::DEFAULT
+        }
+    }
+}
+
+impl Default for LipSyncConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Turns PCM sample buffers into a mouth open ratio. Feed every buffer sent to the speaker and pass the result to
+/// `MouthContext::set_open_ratio` or `Command::SetMouthOpenRatio`.
+#[derive(Clone, Debug, Default)]
+pub struct LipSync {
+    config: LipSyncConfig,
+    open_ratio: f32,
+}
+
+impl LipSync {
+    pub fn new(config: LipSyncConfig) -> Self {
+        Self {
+            config,
+            open_ratio: 0.0,
+        }
+    }
+    pub fn config(&self) -> &LipSyncConfig {
+        &self.config
+    }
+    pub fn set_config(&mut self, config: LipSyncConfig) {
+        self.config = config;
+    }
+    /// Current mouth open ratio in 0.0..=1.0.
+    pub fn open_ratio(&self) -> f32 {
+        self.open_ratio
+    }
+    /// Closes the mouth immediately, e.g. when playback stops.
+    pub fn reset(&mut self) {
+        self.open_ratio = 0.0;
+    }
+    /// Processes a buffer of 16-bit samples and returns the new mouth open ratio.
+    pub fn process_i16(&mut self, samples: &[i16]) -> f32 {
+        let level = match self.config.envelope {
+            Envelope::Rms => rms(samples.iter().map(|&sample| sample as f32 / 32768.0), samples.len()),
+            Envelope::Peak => samples.iter().map(|&sample| (sample as i32).unsigned_abs()).max().unwrap_or(0) as f32 / 32768.0,
+        };
+        self.update(level, samples.len())
+    }
+    /// Processes a buffer of samples in -1.0..=1.0 and returns the new mouth open ratio.
+    pub fn process_f32(&mut self, samples: &[f32]) -> f32 {
+        let level = match self.config.envelope {
+            Envelope::Rms => rms(samples.iter().copied(), samples.len()),
+            Envelope::Peak => samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs())),
+        };
+        self.update(level, samples.len())
+    }
+    /// Lets the mouth close for `milliseconds` of silence, e.g. when no buffer was played.
+    pub fn process_silence(&mut self, milliseconds: u32) -> f32 {
+        self.smooth(0.0, milliseconds as f32)
+    }
+
+    fn update(&mut self, level: f32, sample_count: usize) -> f32 {
+        let target = if level < self.config.noise_gate {
+            0.0
+        } else {
+            ((level - self.config.noise_gate) * self.config.gain).clamp(0.0, 1.0)
+        };
+        let milliseconds = sample_count as f32 * 1000.0 / self.config.sample_rate.max(1) as f32;
+        self.smooth(target, milliseconds)
+    }
+    fn smooth(&mut self, target: f32, milliseconds: f32) -> f32 {
+        let time_constant = if target > self.open_ratio { self.config.attack_milliseconds } else { self.config.release_milliseconds };
+        let factor = if time_constant == 0 { 1.0 } else { 1.0 - (-milliseconds / time_constant as f32).exp() };
+        self.open_ratio = (self.open_ratio + (target - self.open_ratio) * factor).clamp(0.0, 1.0);
+        self.open_ratio
+    }
+}
+
+fn rms(samples: impl Iterator<Item = f32>, count: usize) -> f32 {
+    if count == 0 {
+        return 0.0;
+    }
+    (samples.map(|sample| sample * sample).sum::<f32>() / count as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 16000;
+
+    /// `N` samples of a 440 Hz sine of `amplitude`.
+    fn sine<const N: usize>(amplitude: f32) -> [f32; N] {
+        core::array::from_fn(|index| amplitude * (index as f32 * 2.0 * core::f32::consts::PI * 440.0 / SAMPLE_RATE as f32).sin())
+    }
+
+    #[test]
+    fn sine_above_the_gate_opens_the_mouth_within_the_attack_time() {
+        let config = LipSyncConfig::new(SAMPLE_RATE);
+        let mut lip_sync = LipSync::new(config);
+        let samples = sine::<480>(0.5);
+        assert_eq!(samples.len() as u32, SAMPLE_RATE * config.attack_milliseconds / 1000);
+        // One time constant covers 63% of the way to the target.
+        assert!(lip_sync.process_f32(&samples) > 0.6);
+    }
+
+    #[test]
+    fn noise_below_the_gate_keeps_the_mouth_closed() {
+        let mut lip_sync = LipSync::new(LipSyncConfig::new(SAMPLE_RATE));
+        let mut state = 1u32;
+        let mut noise = [0i16; 160];
+        for sample in noise.iter_mut() {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *sample = ((state >> 16) % 600) as i16 - 300;
+        }
+        for _ in 0..20 {
+            assert_eq!(lip_sync.process_i16(&noise), 0.0);
+        }
+    }
+
+    #[test]
+    fn release_is_slower_than_attack() {
+        let config = LipSyncConfig::new(SAMPLE_RATE);
+        let mut lip_sync = LipSync::new(config);
+        let opened = lip_sync.process_f32(&sine::<480>(0.5));
+        for _ in 0..10 {
+            lip_sync.process_f32(&sine::<480>(0.5));
+        }
+        let open = lip_sync.open_ratio();
+        let closed = open - lip_sync.process_silence(config.attack_milliseconds);
+        assert!(closed < opened, "closed by {closed} while opened by {opened} in the same time");
+    }
+
+    #[test]
+    fn peak_is_at_least_rms() {
+        let config = LipSyncConfig { attack_milliseconds: 0, release_milliseconds: 0, gain: 1.0, ..LipSyncConfig::new(SAMPLE_RATE) };
+        let samples = sine::<160>(0.3);
+        let mut samples_i16 = [0i16; 160];
+        samples_i16.iter_mut().zip(samples).for_each(|(sample, value)| *sample = (value * 32767.0) as i16);
+        let mut rms = LipSync::new(config);
+        let mut peak = LipSync::new(LipSyncConfig { envelope: Envelope::Peak, ..config });
+        assert!(peak.process_f32(&samples) >= rms.process_f32(&samples));
+        assert!(peak.process_i16(&samples_i16) >= rms.process_i16(&samples_i16));
+        assert!(rms.open_ratio() > 0.0);
+    }
+
+    #[test]
+    fn empty_and_full_scale_buffers_do_not_panic() {
+        for envelope in [Envelope::Rms, Envelope::Peak] {
+            let mut lip_sync = LipSync::new(LipSyncConfig { envelope, ..LipSyncConfig::new(SAMPLE_RATE) });
+            assert_eq!(lip_sync.process_i16(&[]), 0.0);
+            assert_eq!(lip_sync.process_f32(&[]), 0.0);
+            let open_ratio = lip_sync.process_i16(&[i16::MIN; 64]);
+            assert!((0.0..=1.0).contains(&open_ratio));
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use embedded_graphics::{draw_target::DrawTarget, prelude::{Dimensions, Point, Size}, primitives::{PointsIter, Rectangle}, Pixel};
+
+/// Clockwise rotation of the avatar on the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+/// Rotation and mirroring of the avatar. Mirroring is applied after the rotation, i.e. along the axes of the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    pub mirror_horizontal: bool,
+    pub mirror_vertical: bool,
+}
+
+impl Orientation {
+    pub const fn new(rotation: Rotation, mirror_horizontal: bool, mirror_vertical: bool) -> Self {
+        Self {
+            rotation,
+            mirror_horizontal,
+            mirror_vertical,
+        }
+    }
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+    pub fn swaps_axes(&self) -> bool {
+        matches!(self.rotation, Rotation::Deg90 | Rotation::Deg270)
+    }
+    /// Size of the area the components are laid out in when they are shown in an area of `size` on the display.
+    pub fn logical_size(&self, size: Size) -> Size {
+        if self.swaps_axes() {
+            Size::new(size.height, size.width)
+        } else {
+            size
+        }
+    }
+}
+
+/// Maps the logical coordinates the components are laid out in onto the coordinates of the display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OrientationTransform {
+    orientation: Orientation,
+    logical: Rectangle,
+    physical: Rectangle,
+}
+
+impl OrientationTransform {
+    /// Transform which shows the logical area in `physical`, an area of the display.
+    pub fn new(orientation: Orientation, physical: Rectangle) -> Self {
+        Self {
+            orientation,
+            logical: Rectangle::new(physical.top_left, orientation.logical_size(physical.size)),
+            physical,
+        }
+    }
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+    pub fn logical_area(&self) -> Rectangle {
+        self.logical
+    }
+    pub fn physical_area(&self) -> Rectangle {
+        self.physical
+    }
+    pub fn is_identity(&self) -> bool {
+        self.orientation.is_identity()
+    }
+    pub fn point(&self, point: Point) -> Point {
+        if self.is_identity() {
+            return point;
+        }
+        let local = point - self.logical.top_left;
+        let logical_width = self.logical.size.width as i32;
+        let logical_height = self.logical.size.height as i32;
+        let (x, y) = match self.orientation.rotation {
+            Rotation::Deg0 => (local.x, local.y),
+            Rotation::Deg90 => (logical_height - 1 - local.y, local.x),
+            Rotation::Deg180 => (logical_width - 1 - local.x, logical_height - 1 - local.y),
+            Rotation::Deg270 => (local.y, logical_width - 1 - local.x),
+        };
+        let x = if self.orientation.mirror_horizontal { self.physical.size.width as i32 - 1 - x } else { x };
+        let y = if self.orientation.mirror_vertical { self.physical.size.height as i32 - 1 - y } else { y };
+        self.physical.top_left + Point::new(x, y)
+    }
+    /// Maps a point on the display back to logical coordinates.
+    pub fn logical_point(&self, point: Point) -> Point {
+        if self.is_identity() {
+            return point;
+        }
+        let local = point - self.physical.top_left;
+        let x = if self.orientation.mirror_horizontal { self.physical.size.width as i32 - 1 - local.x } else { local.x };
+        let y = if self.orientation.mirror_vertical { self.physical.size.height as i32 - 1 - local.y } else { local.y };
+        let logical_width = self.logical.size.width as i32;
+        let logical_height = self.logical.size.height as i32;
+        let (x, y) = match self.orientation.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (y, logical_height - 1 - x),
+            Rotation::Deg180 => (logical_width - 1 - x, logical_height - 1 - y),
+            Rotation::Deg270 => (logical_width - 1 - y, x),
+        };
+        self.logical.top_left + Point::new(x, y)
+    }
+    pub fn rectangle(&self, rectangle: &Rectangle) -> Rectangle {
+        if self.is_identity() {
+            return *rectangle;
+        }
+        match rectangle.bottom_right() {
+            Some(bottom_right) => Rectangle::with_corners(self.point(rectangle.top_left), self.point(bottom_right)),
+            None => Rectangle::new(self.point(rectangle.top_left), Size::zero()),
+        }
+    }
+}
+
+/// Draw target which accepts logical coordinates and draws on `target` in display coordinates.
+///
+/// Contiguous fills are drawn pixel by pixel unless the transform is the identity, so this should wrap in-memory
+/// targets such as [`crate::sprite::Sprite`] rather than the display itself.
+pub struct OrientedTarget<'t, D> {
+    target: &'t mut D,
+    transform: OrientationTransform,
+}
+
+impl<'t, D> OrientedTarget<'t, D> {
+    pub fn new(target: &'t mut D, transform: OrientationTransform) -> Self {
+        Self {
+            target,
+            transform,
+        }
+    }
+}
+
+impl<'t, D: DrawTarget> Dimensions for OrientedTarget<'t, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.transform.logical_area()
+    }
+}
+
+impl<'t, D: DrawTarget> DrawTarget for OrientedTarget<'t, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>> {
+        let transform = self.transform;
+        self.target.draw_iter(pixels.into_iter().map(|Pixel(point, color)| Pixel(transform.point(point), color)))
+    }
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Self::Color> {
+        if self.transform.is_identity() {
+            return self.target.fill_contiguous(area, colors);
+        }
+        let transform = self.transform;
+        self.target.draw_iter(area.points().zip(colors).map(|(point, color)| Pixel(transform.point(point), color)))
+    }
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(&self.transform.rectangle(area), color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHYSICAL: Rectangle = Rectangle::new(Point::new(10, 20), Size::new(4, 3));
+    const ROTATIONS: [Rotation; 4] = [Rotation::Deg0, Rotation::Deg90, Rotation::Deg180, Rotation::Deg270];
+
+    fn orientations() -> impl Iterator<Item = Orientation> {
+        ROTATIONS.into_iter().flat_map(|rotation| {
+            [(false, false), (true, false), (false, true), (true, true)]
+                .into_iter()
+                .map(move |(mirror_horizontal, mirror_vertical)| Orientation::new(rotation, mirror_horizontal, mirror_vertical))
+        })
+    }
+
+    /// Corners of an area in the order top left, top right, bottom right, bottom left.
+    fn corners(area: Rectangle) -> [Point; 4] {
+        let bottom_right = area.bottom_right().unwrap();
+        [area.top_left, Point::new(bottom_right.x, area.top_left.y), bottom_right, Point::new(area.top_left.x, bottom_right.y)]
+    }
+
+    #[test]
+    fn rotation_turns_the_corners_clockwise() {
+        let physical = corners(PHYSICAL);
+        for (turns, rotation) in ROTATIONS.into_iter().enumerate() {
+            let transform = OrientationTransform::new(Orientation::new(rotation, false, false), PHYSICAL);
+            let logical = corners(transform.logical_area());
+            for (index, point) in logical.into_iter().enumerate() {
+                assert_eq!(transform.point(point), physical[(index + turns) % 4], "{rotation:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn mirroring_flips_along_the_display_axes() {
+        let [top_left, top_right, bottom_right, bottom_left] = corners(PHYSICAL);
+        let corner = |orientation| {
+            let transform = OrientationTransform::new(orientation, PHYSICAL);
+            transform.point(transform.logical_area().top_left)
+        };
+        assert_eq!(corner(Orientation::new(Rotation::Deg0, true, false)), top_right);
+        assert_eq!(corner(Orientation::new(Rotation::Deg0, false, true)), bottom_left);
+        assert_eq!(corner(Orientation::new(Rotation::Deg0, true, true)), bottom_right);
+        // The rotation moves the corner to the top right first, and the horizontal mirror moves it back.
+        assert_eq!(corner(Orientation::new(Rotation::Deg90, true, false)), top_left);
+        assert_eq!(corner(Orientation::new(Rotation::Deg180, false, true)), top_right);
+        assert_eq!(corner(Orientation::new(Rotation::Deg270, false, true)), top_left);
+    }
+
+    #[test]
+    fn points_map_one_to_one_onto_the_physical_area() {
+        for orientation in orientations() {
+            let transform = OrientationTransform::new(orientation, PHYSICAL);
+            assert_eq!(transform.logical_area().size, orientation.logical_size(PHYSICAL.size));
+            let mut is_hit = [false; 12];
+            for point in transform.logical_area().points() {
+                let physical = transform.point(point);
+                assert!(PHYSICAL.contains(physical), "{orientation:?} maps {point:?} to {physical:?}");
+                assert_eq!(transform.logical_point(physical), point, "{orientation:?}");
+                let local = physical - PHYSICAL.top_left;
+                is_hit[(local.y * 4 + local.x) as usize] = true;
+            }
+            assert!(is_hit.iter().all(|is_hit| *is_hit), "{orientation:?}");
+        }
+    }
+
+    #[test]
+    fn rectangles_cover_their_mapped_points() {
+        let rectangles = [
+            Rectangle::new(Point::new(10, 20), Size::new(1, 1)),
+            Rectangle::new(Point::new(11, 20), Size::new(2, 3)),
+            Rectangle::new(Point::new(10, 21), Size::new(3, 2)),
+        ];
+        for orientation in orientations() {
+            let transform = OrientationTransform::new(orientation, PHYSICAL);
+            for rectangle in rectangles {
+                let mapped = transform.rectangle(&rectangle);
+                assert_eq!(mapped.size, orientation.logical_size(rectangle.size), "{orientation:?} {rectangle:?}");
+                assert!(rectangle.points().all(|point| mapped.contains(transform.point(point))), "{orientation:?} {rectangle:?}");
+            }
+            assert_eq!(transform.rectangle(&transform.logical_area()), PHYSICAL, "{orientation:?}");
+        }
+    }
+}
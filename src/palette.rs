@@ -12,11 +12,14 @@ pub enum BasicPaletteKey {
     Background,
     BalloonForeground,
     BalloonBackground,
+    /// Fill of the sclera and the catchlight of `EyeStyle::PupilAndSclera`.
+    Sclera,
+    Pupil,
 }
 
-impl<'a> Into<usize> for &'a BasicPaletteKey {
-    fn into(self) -> usize {
-        (*self).into()
+impl From<&BasicPaletteKey> for usize {
+    fn from(key: &BasicPaletteKey) -> Self {
+        (*key).into()
     }
 }
 
@@ -40,7 +43,7 @@ impl<Key, Color: PixelColor + Default, const SIZE: usize> Default for ArrayPalet
     fn default() -> Self {
         Self {
             colors: [Default::default(); SIZE],
-            key: PhantomData::default(),
+            key: PhantomData,
         }
     }
 }
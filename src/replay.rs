@@ -0,0 +1,202 @@
+use core::str::FromStr;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use embedded_graphics::prelude::PixelColor;
+
+use crate::{Animator, Avatar, Command, FaceControl};
+use crate::components::face::DrawContext;
+
+/// Command applied to an avatar before it rendered frame `frame`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedCommand<String> {
+    pub frame: u64,
+    pub command: Command<String>,
+}
+
+/// Records the commands applied to a deterministic avatar so that the session can be replayed with a [`Player`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct Recorder<String> {
+    seed: u64,
+    commands: Vec<RecordedCommand<String>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<String: Clone> Recorder<String> {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            commands: Vec::new(),
+        }
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn commands(&self) -> &[RecordedCommand<String>] {
+        &self.commands
+    }
+    pub fn into_commands(self) -> Vec<RecordedCommand<String>> {
+        self.commands
+    }
+    /// Makes `avatar` deterministic with the seed of the recording. Call it on a freshly created avatar.
+    pub fn start<'a, Color, RootAnimator>(&self, avatar: &mut Avatar<'a, Color, String, RootAnimator>)
+        where
+            Color: PixelColor + From<Color::Raw> + Into<Color::Raw>,
+            String: AsRef<str> + FromStr + Clone,
+            RootAnimator: Animator<DrawContext<Color, String>>,
+    {
+        avatar.set_deterministic(self.seed);
+    }
+    /// Applies `command` to `avatar` and records it.
+    pub fn apply<'a, Color, RootAnimator>(&mut self, avatar: &mut Avatar<'a, Color, String, RootAnimator>, command: Command<String>)
+        where
+            Color: PixelColor + From<Color::Raw> + Into<Color::Raw>,
+            String: AsRef<str> + FromStr + Clone,
+            RootAnimator: Animator<DrawContext<Color, String>> + FaceControl,
+    {
+        self.commands.push(RecordedCommand {
+            frame: avatar.frame_number(),
+            command: command.clone(),
+        });
+        avatar.apply(command);
+    }
+}
+
+/// Replays recorded commands. Start the avatar with `Avatar::set_deterministic` and the recorded seed, and call
+/// `apply_due` before each `run` to reproduce the recorded frames.
+#[derive(Clone, Debug)]
+pub struct Player<'r, String> {
+    commands: &'r [RecordedCommand<String>],
+    position: usize,
+}
+
+impl<'r, String: Clone> Player<'r, String> {
+    pub fn new(commands: &'r [RecordedCommand<String>]) -> Self {
+        Self {
+            commands,
+            position: 0,
+        }
+    }
+    /// Applies the commands which were recorded before the frame `avatar` renders next.
+    pub fn apply_due<'a, Color, RootAnimator>(&mut self, avatar: &mut Avatar<'a, Color, String, RootAnimator>)
+        where
+            Color: PixelColor + From<Color::Raw> + Into<Color::Raw>,
+            String: AsRef<str> + FromStr + Clone,
+            RootAnimator: Animator<DrawContext<Color, String>> + FaceControl,
+    {
+        while let Some(recorded) = self.commands.get(self.position).filter(|recorded| recorded.frame <= avatar.frame_number()) {
+            avatar.apply(recorded.command.clone());
+            self.position += 1;
+        }
+    }
+    /// Returns true once every command has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.commands.len()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use core::{cell::Cell, convert::Infallible};
+
+    use alloc::{string::String, vec, vec::Vec};
+    use embedded_graphics::{pixelcolor::BinaryColor, prelude::{DrawTarget, OriginDimensions, Pixel, Size}, primitives::Rectangle};
+
+    use super::*;
+    use crate::{BasicPaletteKey, Expression, Palette, Timer};
+
+    struct MockTimer {
+        now: Cell<u64>,
+    }
+
+    impl Timer for MockTimer {
+        fn timestamp_milliseconds(&self) -> u64 {
+            self.now.get()
+        }
+    }
+
+    struct Framebuffer {
+        pixels: Vec<BinaryColor>,
+    }
+
+    impl OriginDimensions for Framebuffer {
+        fn size(&self) -> Size {
+            Size::new(320, 240)
+        }
+    }
+
+    impl DrawTarget for Framebuffer {
+        type Color = BinaryColor;
+        type Error = Infallible;
+        fn draw_iter<I: IntoIterator<Item = Pixel<BinaryColor>>>(&mut self, pixels: I) -> Result<(), Self::Error> {
+            for Pixel(point, color) in pixels {
+                if (0..320).contains(&point.x) && (0..240).contains(&point.y) {
+                    self.pixels[(point.y * 320 + point.x) as usize] = color;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Framebuffer and damage list of a rendered frame.
+    type Frame = (Vec<BinaryColor>, Vec<Rectangle>);
+
+    fn avatar() -> Avatar<'static, BinaryColor, String> {
+        let mut context = DrawContext::default();
+        context.palette.set_color(&BasicPaletteKey::Primary, BinaryColor::On);
+        context.palette.set_color(&BasicPaletteKey::Background, BinaryColor::Off);
+        context.palette.set_color(&BasicPaletteKey::BalloonForeground, BinaryColor::On);
+        Avatar::new(context, 30)
+    }
+
+    /// Calls `run` every 5 ms for three seconds, calling `before_run` first, and returns the rendered frames.
+    fn session(avatar: &mut Avatar<'static, BinaryColor, String>, mut before_run: impl FnMut(&mut Avatar<'static, BinaryColor, String>)) -> Vec<Frame> {
+        let timer = MockTimer { now: Cell::new(1000) };
+        let mut display = Framebuffer { pixels: vec![BinaryColor::Off; 320 * 240] };
+        let mut frames = Vec::new();
+        for step in 0..600 {
+            timer.now.set(1000 + step * 5);
+            before_run(avatar);
+            if avatar.run(&mut display, &timer).unwrap().rendered {
+                frames.push((display.pixels.clone(), avatar.damage().to_vec()));
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn replay_renders_the_recorded_frames() {
+        let script = [
+            (5, Command::SetExpression(Expression::Happy)),
+            (12, Command::Say(String::from("Hello"))),
+            (30, Command::Blink),
+            (41, Command::LookAt { horizontal: 0.8, vertical: -0.5 }),
+            (41, Command::SetPupilSize(0.5)),
+            (60, Command::Wink { is_left: true }),
+            (75, Command::SetText(None)),
+        ];
+        let mut recorder = Recorder::new(42);
+        let mut recorded_avatar = avatar();
+        recorder.start(&mut recorded_avatar);
+        let mut position = 0;
+        let recorded = session(&mut recorded_avatar, |avatar| {
+            while let Some((_, command)) = script.get(position).filter(|(frame, _)| *frame <= avatar.frame_number()) {
+                recorder.apply(avatar, command.clone());
+                position += 1;
+            }
+        });
+        assert_eq!(recorder.commands().len(), script.len());
+        assert!(recorded.windows(2).any(|frames| frames[0] != frames[1]));
+
+        let mut replayed_avatar = avatar();
+        replayed_avatar.set_deterministic(recorder.seed());
+        let mut player = Player::new(recorder.commands());
+        let replayed = session(&mut replayed_avatar, |avatar| player.apply_due(avatar));
+        assert!(player.is_finished());
+        assert_eq!(replayed.len(), recorded.len());
+        for (index, (replayed, recorded)) in replayed.iter().zip(&recorded).enumerate() {
+            assert!(replayed == recorded, "frame {index} differs");
+        }
+    }
+}
@@ -0,0 +1,340 @@
+#[allow(unused)]
+use micromath::F32Ext as _;
+
+use crate::{AnimationEvent, Animator, EventContext, FaceControl, FrameCounter, Instant, MouthShape, TransitionAnimator, TransitionStep, Viseme, VisemeTransitionAnimator};
+use crate::components::{balloon::BalloonContext, mouth::MouthContext};
+
+/// Mouth position for a span of a spoken text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeechCue {
+    pub viseme: Viseme,
+    /// Open ratio at the loudest point of the cue. Pauses are 0.0.
+    pub open_ratio: f32,
+    pub start_milliseconds: u32,
+    pub duration_milliseconds: u32,
+}
+
+/// Splits a text in romaji, kana or English letters into timed [`SpeechCue`]s, one character per slot of
+/// `1 / characters_per_second` seconds. Punctuation adds pauses.
+#[derive(Clone, Copy, Debug)]
+pub struct SpeechTimeline<'t> {
+    text: &'t str,
+    characters_per_second: f32,
+}
+
+impl<'t> SpeechTimeline<'t> {
+    pub fn new(text: &'t str, characters_per_second: f32) -> Self {
+        Self {
+            text,
+            characters_per_second,
+        }
+    }
+    fn slot_milliseconds(&self) -> f32 {
+        1000.0 / self.characters_per_second.max(0.1)
+    }
+    /// Computes the cue which starts at `cursor` and moves the cursor past it.
+    fn next_cue(&self, cursor: &mut SpeechCursor) -> Option<SpeechCue> {
+        let mut chars = self.text.get(cursor.offset..)?.chars();
+        let c = chars.next()?;
+        let (viseme, slots) = match sound_of(c, chars, cursor.previous) {
+            Sound::Viseme(viseme) => (Some(viseme), 1),
+            Sound::Pause(slots) => (None, slots),
+        };
+        let slot_milliseconds = self.slot_milliseconds();
+        let start = (cursor.slot as f32 * slot_milliseconds) as u32;
+        cursor.offset += c.len_utf8();
+        cursor.slot += slots;
+        cursor.previous = viseme;
+        Some(SpeechCue {
+            viseme: viseme.unwrap_or(Viseme::Closed),
+            open_ratio: viseme.map_or(0.0, open_ratio_of),
+            start_milliseconds: start,
+            duration_milliseconds: (cursor.slot as f32 * slot_milliseconds) as u32 - start,
+        })
+    }
+    pub fn cues(&self) -> impl Iterator<Item = SpeechCue> + 't {
+        let timeline = *self;
+        let mut cursor = SpeechCursor::default();
+        core::iter::from_fn(move || timeline.next_cue(&mut cursor))
+    }
+    pub fn duration_milliseconds(&self) -> u32 {
+        self.cues().last().map_or(0, |cue| cue.start_milliseconds + cue.duration_milliseconds)
+    }
+    /// Cue which is spoken `elapsed` milliseconds after the start, or `None` once the text is over.
+    pub fn cue_at(&self, elapsed: u32) -> Option<SpeechCue> {
+        self.cues().find(|cue| elapsed < cue.start_milliseconds + cue.duration_milliseconds)
+    }
+    /// Same as `cue_at`, but continues from the cue `cursor` found last instead of scanning the text from its
+    /// beginning. `elapsed` must not go backwards for the same cursor.
+    pub fn advance(&self, cursor: &mut SpeechCursor, elapsed: u32) -> Option<SpeechCue> {
+        loop {
+            match cursor.cue {
+                Some(cue) if elapsed < cue.start_milliseconds + cue.duration_milliseconds => return Some(cue),
+                _ => cursor.cue = Some(self.next_cue(cursor)?),
+            }
+        }
+    }
+}
+
+/// Position in the cues of a [`SpeechTimeline`], which does not borrow the text so that it can be kept across frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpeechCursor {
+    /// Byte offset of the character after the current cue.
+    offset: usize,
+    /// Slot at which the cue after the current one starts.
+    slot: u32,
+    previous: Option<Viseme>,
+    cue: Option<SpeechCue>,
+}
+
+enum Sound {
+    Viseme(Viseme),
+    Pause(u32),
+}
+
+fn vowel_of(c: char) -> Option<Viseme> {
+    match c.to_ascii_lowercase() {
+        'a' => Some(Viseme::A),
+        'i' => Some(Viseme::I),
+        'u' => Some(Viseme::U),
+        'e' => Some(Viseme::E),
+        'o' => Some(Viseme::O),
+        _ => None,
+    }
+}
+
+/// Vowel of a hiragana or katakana character.
+fn kana_vowel_of(c: char) -> Option<Viseme> {
+    const ROWS: [(&str, Viseme); 5] = [
+        ("ぁあかがさざただなはばぱまゃやらゎわ", Viseme::A),
+        ("ぃいきぎしじちぢにひびぴみりゐ", Viseme::I),
+        ("ぅうくぐすずつづぬふぶぷむゅゆるゔ", Viseme::U),
+        ("ぇえけげせぜてでねへべぺめれゑ", Viseme::E),
+        ("ぉおこごそぞとどのほぼぽもょよろを", Viseme::O),
+    ];
+    let hiragana = match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60)?,
+        _ => c,
+    };
+    ROWS.iter().find(|(row, _)| row.contains(hiragana)).map(|(_, viseme)| *viseme)
+}
+
+fn sound_of(c: char, mut rest: core::str::Chars, previous: Option<Viseme>) -> Sound {
+    if let Some(vowel) = vowel_of(c) {
+        return Sound::Viseme(vowel);
+    }
+    if c.is_ascii_alphabetic() {
+        let next = rest.clone().next().map(|next| next.to_ascii_lowercase());
+        // Consonants anticipate the vowel which follows them in the word.
+        let next_vowel = rest.find(|c| !c.is_ascii_alphabetic() || vowel_of(*c).is_some()).and_then(vowel_of);
+        return Sound::Viseme(match c.to_ascii_lowercase() {
+            'm' | 'b' | 'p' => Viseme::Closed,
+            'w' | 'q' => Viseme::U,
+            'f' | 'v' => Viseme::I,
+            'n' if !next.is_some_and(|next| vowel_of(next).is_some() || next == 'y') => Viseme::Closed,
+            'y' if next_vowel.is_none() => Viseme::I,
+            _ => next_vowel.unwrap_or(Viseme::E),
+        });
+    }
+    match c {
+        'ん' | 'ン' | 'っ' | 'ッ' => Sound::Viseme(Viseme::Closed),
+        'ー' | '〜' | '～' => previous.map_or(Sound::Pause(1), Sound::Viseme),
+        '.' | '!' | '?' | '。' | '！' | '？' => Sound::Pause(3),
+        ',' | ';' | ':' | '、' | '，' => Sound::Pause(2),
+        _ if c.is_whitespace() || c.is_ascii() => Sound::Pause(1),
+        '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF0F}' => Sound::Pause(1),
+        _ => match kana_vowel_of(c) {
+            Some(vowel) => Sound::Viseme(vowel),
+            // Kanji and other characters have no known reading, so any vowel looks plausible.
+            None => Sound::Viseme(Viseme::ALL[c as usize % 5]),
+        },
+    }
+}
+
+fn open_ratio_of(viseme: Viseme) -> f32 {
+    match viseme {
+        Viseme::A => 1.0,
+        Viseme::O => 0.75,
+        Viseme::E => 0.6,
+        Viseme::U => 0.4,
+        Viseme::I => 0.35,
+        Viseme::Closed => 0.0,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpeechConfig {
+    pub characters_per_second: f32,
+    /// Time the balloon stays after the last character before it is cleared.
+    pub hold_milliseconds: u32,
+}
+
+impl SpeechConfig {
+    pub const DEFAULT: Self = Self {
+        characters_per_second: 8.0,
+        hold_milliseconds: 1000,
+    };
+}
+
+impl Default for SpeechConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Moves the mouth along the text of the balloon after `start` is called and clears the balloon when it is over.
+#[derive(Debug)]
+pub struct SpeechAnimator {
+    config: SpeechConfig,
+    is_speaking: bool,
+    origin: Option<Instant>,
+    /// Cue reached so far and the length of the text, both computed at the first frame of the speech.
+    cursor: SpeechCursor,
+    duration_milliseconds: u32,
+    /// Blends the shape between the visemes of consecutive cues.
+    transition: TransitionAnimator<MouthShape, Viseme>,
+    /// Blended shape of the current cue before it is opened and closed.
+    shape: MouthShape,
+}
+
+impl SpeechAnimator {
+    /// Interval at which the animator is polled while it does not speak.
+    const IDLE_MILLISECONDS: u64 = 1000;
+
+    pub fn new(config: SpeechConfig) -> Self {
+        Self {
+            config,
+            is_speaking: false,
+            origin: None,
+            cursor: SpeechCursor::default(),
+            duration_milliseconds: 0,
+            transition: TransitionAnimator::with_duration(VisemeTransitionAnimator::DEFAULT_DURATION_MILLISECONDS, MouthShape::CLOSED),
+            shape: MouthShape::CLOSED,
+        }
+    }
+    pub fn config(&self) -> &SpeechConfig {
+        &self.config
+    }
+    pub fn set_config(&mut self, config: SpeechConfig) {
+        self.config = config;
+    }
+    pub fn is_speaking(&self) -> bool {
+        self.is_speaking
+    }
+    /// Speaks the text of the balloon from its beginning at the next frame.
+    pub fn start(&mut self) {
+        self.is_speaking = true;
+        self.origin = None;
+        self.transition = TransitionAnimator::with_duration(self.transition.duration_milliseconds(), MouthShape::CLOSED);
+    }
+    /// Stops moving the mouth and keeps the balloon.
+    pub fn stop(&mut self) {
+        self.is_speaking = false;
+        self.origin = None;
+    }
+}
+
+impl Default for SpeechAnimator {
+    fn default() -> Self {
+        Self::new(SpeechConfig::DEFAULT)
+    }
+}
+
+impl<'a, Context: BalloonContext<'a> + MouthContext<'a> + EventContext> Animator<Context> for SpeechAnimator {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if !self.is_speaking {
+            return counter.after_milliseconds(Self::IDLE_MILLISECONDS);
+        }
+        let Some(timeline) = context.text().map(|text| SpeechTimeline::new(text, self.config.characters_per_second)) else {
+            // The balloon was cleared by someone else.
+            self.stop();
+            context.set_viseme(None);
+            context.set_open_ratio(0.0);
+            return counter.after_milliseconds(Self::IDLE_MILLISECONDS);
+        };
+        let origin = match self.origin {
+            Some(origin) => origin,
+            None => {
+                self.cursor = SpeechCursor::default();
+                self.duration_milliseconds = timeline.duration_milliseconds();
+                *self.origin.insert(counter.time())
+            },
+        };
+        let elapsed = counter.time().milliseconds_since(&origin);
+        if let Some(cue) = timeline.advance(&mut self.cursor, elapsed) {
+            // Open and close the mouth once per cue so that repeated vowels are visible as well.
+            let phase = (elapsed - cue.start_milliseconds) as f32 / cue.duration_milliseconds.max(1) as f32;
+            let envelope = 0.5 + 0.5 * (phase * core::f32::consts::PI).sin();
+            let to = MouthShape::of(cue.viseme);
+            self.shape = match self.transition.step(counter.time(), cue.viseme, self.shape, &to) {
+                TransitionStep::Blending(shape) => shape,
+                _ => to,
+            };
+            context.set_viseme(Some(cue.viseme));
+            context.set_open_ratio(cue.open_ratio * envelope);
+            // The mouth is drawn from the shape while a viseme is set, so the envelope is applied to its height.
+            context.set_mouth_shape(Some(MouthShape { height: self.shape.height * envelope, ..self.shape }));
+            return counter.after_frames(1);
+        }
+        context.set_viseme(None);
+        context.set_open_ratio(0.0);
+        let end = self.duration_milliseconds.saturating_add(self.config.hold_milliseconds);
+        if elapsed < end {
+            return counter.after_milliseconds((end - elapsed) as u64);
+        }
+        self.stop();
+        context.set_text(None);
+        context.push_event(AnimationEvent::SpeechFinished);
+        counter.after_milliseconds(Self::IDLE_MILLISECONDS)
+    }
+    fn is_finished(&self) -> bool {
+        !self.is_speaking
+    }
+    fn reset(&mut self) {
+        self.stop();
+    }
+}
+
+impl FaceControl for SpeechAnimator {
+    fn start_speech(&mut self) {
+        self.start()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    use super::*;
+    use crate::AnimationRunner;
+    use crate::components::face::DrawContext;
+
+    #[test]
+    fn repeated_vowels_open_and_close_the_mouth() {
+        let mut runner = AnimationRunner::new(DrawContext::<BinaryColor, alloc::string::String>::default(), 30, SpeechAnimator::default());
+        runner.context().set_text(Some("aaaa"));
+        runner.animator().start();
+        let open = MouthShape::of(Viseme::A).height;
+        let mut heights = alloc::vec::Vec::new();
+        // Four characters at 8 characters per second.
+        for _ in 0..15 {
+            runner.next();
+            let context = runner.context();
+            assert_eq!(context.viseme(), Some(Viseme::A));
+            heights.push(context.mouth_shape().unwrap().height);
+        }
+        let rises = heights.windows(2).filter(|pair| pair[1] > pair[0]).count();
+        let falls = heights.windows(2).filter(|pair| pair[1] < pair[0]).count();
+        assert!(rises >= 4 && falls >= 4, "{heights:?}");
+        assert!(heights.iter().all(|&height| height <= open && height >= 0.5 * open));
+    }
+
+    #[test]
+    fn cursor_finds_the_same_cues_as_cue_at() {
+        let timeline = SpeechTimeline::new("Konnichiwa, こんにちは。ＡＩ ー です!", 8.0);
+        let mut cursor = SpeechCursor::default();
+        for elapsed in (0..timeline.duration_milliseconds() + 200).step_by(7) {
+            assert_eq!(timeline.advance(&mut cursor, elapsed), timeline.cue_at(elapsed), "{elapsed} ms");
+        }
+    }
+}
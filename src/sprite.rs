@@ -1,6 +1,14 @@
 use core::marker::PhantomData;
 
-use embedded_graphics::{Drawable, draw_target::DrawTarget, primitives::Rectangle, prelude::{PixelColor, Point, Size, Dimensions, RawData, IntoStorage}, Pixel};
+use embedded_graphics::{Drawable, draw_target::{DrawTarget, DrawTargetExt}, primitives::Rectangle, prelude::{PixelColor, Dimensions}, Pixel};
+
+use crate::orientation::{OrientationTransform, OrientedTarget};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpriteError {
+    BufferTooSmall,
+    Unaligned,
+}
 
 pub struct Sprite<'a, Color: PixelColor> {
     buffer: &'a mut [Color],
@@ -11,10 +19,10 @@ pub struct Sprite<'a, Color: PixelColor> {
 impl<'a, Color> Sprite<'a, Color> 
     where Color: PixelColor
 {
-    pub fn new(buffer: &'a mut [Color], geometry: Rectangle) -> Result<Self, ()> {
+    pub fn new(buffer: &'a mut [Color], geometry: Rectangle) -> Result<Self, SpriteError> {
         let required_length = (geometry.size.width * geometry.size.height) as usize;
         if buffer.len() < required_length {
-            return Err(())
+            return Err(SpriteError::BufferTooSmall)
         }
         Ok(Self {
             buffer,
@@ -24,11 +32,11 @@ impl<'a, Color> Sprite<'a, Color>
     }
     pub const fn unaligned_buffer_size(width: u32, height: u32) -> usize {
         let required_length = (width * height) as usize;
-        let required_bytes = required_length * core::mem::size_of::<<<Color as PixelColor>::Raw as RawData>::Storage>();
-        let alignment = core::mem::align_of::<<<Color as PixelColor>::Raw as RawData>::Storage>();
+        let required_bytes = required_length * core::mem::size_of::<Color>();
+        let alignment = core::mem::align_of::<Color>();
         required_bytes + alignment - 1
     }
-    pub fn new_unaligned(buffer: &'a mut[u8], geometry: Rectangle) -> Result<Self, ()> {
+    pub fn new_unaligned(buffer: &'a mut[u8], geometry: Rectangle) -> Result<Self, SpriteError> {
         let required_length = (geometry.size.width * geometry.size.height) as usize;
         let required_bytes = required_length * core::mem::size_of::<Color>();
         let buffer = unsafe {
@@ -36,11 +44,11 @@ impl<'a, Color> Sprite<'a, Color>
             let alignment = core::mem::align_of::<Color>();
             let offset = ptr.align_offset(alignment);
             if offset ==  usize::MAX {
-                return Err(());
+                return Err(SpriteError::Unaligned);
             }
             let ptr_aligned = ptr.add(offset);
             if buffer.len() < required_bytes + offset {
-                return Err(());
+                return Err(SpriteError::BufferTooSmall);
             }
             let ptr_color = ptr_aligned as *mut Color;
             core::slice::from_raw_parts_mut(ptr_color, required_length)
@@ -140,7 +148,88 @@ impl<'a, Color: PixelColor + From<Color::Raw>> Drawable for Sprite<'a, Color>
     fn draw<D>(&self, target: &mut D) -> Result<Self::Output, D::Error>
         where
             D: DrawTarget<Color = Self::Color> {
-        target.fill_contiguous(&self.geometry, self.buffer.as_ref().into_iter().map(|c| c.clone()))?;
+        target.fill_contiguous(&self.geometry, self.buffer.iter().copied())?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Drawable which is rendered into an off-screen sprite covering its bounding box and then transferred to the
+/// draw target at once.
+pub trait SpriteDrawable: Dimensions {
+    type Color: PixelColor;
+    fn background_color(&self) -> Self::Color;
+    /// Draws the contents on a target which has already been filled with the background color.
+    fn draw_sprite<D: DrawTarget<Color = Self::Color>>(&self, target: &mut D) -> Result<(), D::Error>;
+}
+
+/// Draws `drawable` using `scratch` as the sprite buffer.
+///
+/// If `scratch` is too small for the bounding box of the drawable, it is drawn directly to the target instead,
+/// which may cause visible flicker.
+pub fn draw_with_scratch<T, D>(drawable: &T, target: &mut D, scratch: &mut [u8]) -> Result<(), D::Error>
+    where
+        T: SpriteDrawable,
+        T::Color: From<<T::Color as PixelColor>::Raw> + Into<<T::Color as PixelColor>::Raw>,
+        D: DrawTarget<Color = T::Color>,
+{
+    draw_with_scratch_oriented(drawable, target, scratch, &OrientationTransform::default())
+}
+
+/// Draws `drawable`, which is laid out in the logical coordinates of `transform`, using `scratch` as the sprite buffer.
+///
+/// The sprite is stored in display order, so it is still transferred to the target with a single `fill_contiguous`.
+pub fn draw_with_scratch_oriented<T, D>(drawable: &T, target: &mut D, scratch: &mut [u8], transform: &OrientationTransform) -> Result<(), D::Error>
+    where
+        T: SpriteDrawable,
+        T::Color: From<<T::Color as PixelColor>::Raw> + Into<<T::Color as PixelColor>::Raw>,
+        D: DrawTarget<Color = T::Color>,
+{
+    let bounding_box = transform.rectangle(&drawable.bounding_box());
+    if let Ok(mut sprite) = Sprite::<T::Color>::new_unaligned(scratch, bounding_box) {
+        sprite.clear(drawable.background_color()).ok();
+        drawable.draw_sprite(&mut OrientedTarget::new(&mut sprite, *transform)).ok();
+        sprite.draw(target)
+    } else {
+        let mut target = target.clipped(&bounding_box);
+        target.fill_solid(&bounding_box, drawable.background_color())?;
+        drawable.draw_sprite(&mut OrientedTarget::new(&mut target, *transform))
+    }
+}
+
+/// Draws `drawable` through a temporary sprite buffer, or directly to the target if `alloc` is not available.
+pub fn draw_buffered<T, D>(drawable: &T, target: &mut D) -> Result<(), D::Error>
+    where
+        T: SpriteDrawable,
+        T::Color: From<<T::Color as PixelColor>::Raw> + Into<<T::Color as PixelColor>::Raw>,
+        D: DrawTarget<Color = T::Color>,
+{
+    #[cfg(feature = "alloc")]
+    let mut buffer = crate::util::prepare_sprite_buffer::<T::Color>(drawable.bounding_box());
+    #[cfg(not(feature = "alloc"))]
+    let mut buffer = [];
+    draw_with_scratch(drawable, target, &mut buffer)
+}
+
+/// Statically allocated scratch buffer for rendering without an allocator.
+///
+/// Use [`crate::Avatar::scratch_size`] to find the required `SIZE` for a layout.
+pub struct StaticScratch<const SIZE: usize> {
+    buffer: [u8; SIZE],
+}
+
+impl<const SIZE: usize> StaticScratch<SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; SIZE],
+        }
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.buffer
+    }
+}
+
+impl<const SIZE: usize> Default for StaticScratch<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
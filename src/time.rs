@@ -0,0 +1,48 @@
+/// Monotonic millisecond timestamp.
+///
+/// Timestamps are kept modulo 2^32 and compared with wrapping arithmetic, so both `u64` and wrapping `u32`
+/// millisecond clocks can be used as a time source. Two instants can be ordered as long as they are less than
+/// about 24 days apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Instant {
+    milliseconds: u32,
+}
+
+impl Instant {
+    pub const fn from_millis(milliseconds: u64) -> Self {
+        Self { milliseconds: milliseconds as u32 }
+    }
+    pub const fn from_millis_u32(milliseconds: u32) -> Self {
+        Self { milliseconds }
+    }
+    pub const fn as_millis(&self) -> u32 {
+        self.milliseconds
+    }
+    pub const fn after_milliseconds(&self, milliseconds: u64) -> Self {
+        Self { milliseconds: self.milliseconds.wrapping_add(milliseconds as u32) }
+    }
+    /// Signed distance from `earlier` to `self` in milliseconds.
+    pub const fn signed_milliseconds_since(&self, earlier: &Instant) -> i32 {
+        self.milliseconds.wrapping_sub(earlier.milliseconds) as i32
+    }
+    /// Milliseconds elapsed since `earlier`, or zero if `earlier` is in the future.
+    pub const fn milliseconds_since(&self, earlier: &Instant) -> u32 {
+        let diff = self.signed_milliseconds_since(earlier);
+        if diff < 0 { 0 } else { diff as u32 }
+    }
+    pub const fn is_after(&self, other: &Instant) -> bool {
+        self.signed_milliseconds_since(other) >= 0
+    }
+}
+
+impl From<u64> for Instant {
+    fn from(milliseconds: u64) -> Self {
+        Self::from_millis(milliseconds)
+    }
+}
+
+impl From<u32> for Instant {
+    fn from(milliseconds: u32) -> Self {
+        Self::from_millis_u32(milliseconds)
+    }
+}
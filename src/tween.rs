@@ -0,0 +1,248 @@
+#[allow(unused)]
+use micromath::F32Ext as _;
+
+use crate::{Animator, FaceControl, FrameCounter, Instant};
+use crate::components::{face::FaceContext, eye::EyeContext, mouth::MouthContext};
+
+/// Values which can be blended linearly, e.g. by a [`crate::TransitionAnimator`].
+pub trait Lerp {
+    /// Blends from `self` at `t` = 0.0 to `other` at `t` = 1.0.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// Easing curves which map the progress of a tween segment from 0.0..=1.0 to the interpolation factor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Pulls back slightly before moving towards the target.
+    BackIn,
+    /// Overshoots the target slightly before settling.
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+}
+
+impl Easing {
+    const BACK: f32 = 1.70158;
+    const BACK_IN_OUT: f32 = Self::BACK * 1.525;
+    const ELASTIC: f32 = 2.0 * core::f32::consts::PI / 3.0;
+    const ELASTIC_IN_OUT: f32 = 2.0 * core::f32::consts::PI / 4.5;
+
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+            Self::CubicIn => t * t * t,
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+            Self::BackIn => (Self::BACK + 1.0) * t * t * t - Self::BACK * t * t,
+            Self::BackOut => 1.0 + (Self::BACK + 1.0) * (t - 1.0).powi(3) + Self::BACK * (t - 1.0).powi(2),
+            Self::BackInOut => if t < 0.5 {
+                (2.0 * t).powi(2) * ((Self::BACK_IN_OUT + 1.0) * 2.0 * t - Self::BACK_IN_OUT) / 2.0
+            } else {
+                ((2.0 * t - 2.0).powi(2) * ((Self::BACK_IN_OUT + 1.0) * (2.0 * t - 2.0) + Self::BACK_IN_OUT) + 2.0) / 2.0
+            },
+            _ if t == 0.0 || t == 1.0 => t,
+            Self::ElasticIn => -(2.0f32).powf(10.0 * t - 10.0) * ((10.0 * t - 10.75) * Self::ELASTIC).sin(),
+            Self::ElasticOut => (2.0f32).powf(-10.0 * t) * ((10.0 * t - 0.75) * Self::ELASTIC).sin() + 1.0,
+            Self::ElasticInOut => if t < 0.5 {
+                -((2.0f32).powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * Self::ELASTIC_IN_OUT).sin()) / 2.0
+            } else {
+                (2.0f32).powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * Self::ELASTIC_IN_OUT).sin() / 2.0 + 1.0
+            },
+        }
+    }
+}
+
+/// Value of a tween at `time_milliseconds` after the start. `easing` shapes the segment which ends at this keyframe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time_milliseconds: u32,
+    pub value: f32,
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub const fn new(time_milliseconds: u32, value: f32, easing: Easing) -> Self {
+        Self {
+            time_milliseconds,
+            value,
+            easing,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TweenMode {
+    /// Play the keyframes once and hold the last value.
+    #[default]
+    Once,
+    /// Jump back to the first keyframe after the last one.
+    Loop,
+    /// Play the keyframes forwards and then backwards.
+    PingPong,
+}
+
+/// Parameter of the context which a tween drives.
+pub trait TweenTarget<Context> {
+    fn set(&self, context: &mut Context, value: f32);
+}
+
+impl<Context> TweenTarget<Context> for fn(&mut Context, f32) {
+    fn set(&self, context: &mut Context, value: f32) {
+        self(context, value)
+    }
+}
+
+/// Parameters of a [`FaceContext`] which can be tweened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaceParameter {
+    GazeHorizontal,
+    GazeVertical,
+    EyeOpenRatio,
+    PupilSize,
+    MouthOpenRatio,
+    Breath,
+    HeadAngle,
+    HeadOffsetX,
+    HeadOffsetY,
+    HeadScale,
+}
+
+impl<'a, Context: FaceContext<'a>> TweenTarget<Context> for FaceParameter {
+    fn set(&self, context: &mut Context, value: f32) {
+        let mut head = context.head();
+        match self {
+            Self::GazeHorizontal => context.set_horizontal(value),
+            Self::GazeVertical => context.set_vertical(value),
+            Self::EyeOpenRatio => EyeContext::set_open_ratio(context, value),
+            Self::PupilSize => context.set_pupil_size(value),
+            Self::MouthOpenRatio => MouthContext::set_open_ratio(context, value),
+            Self::Breath => context.set_breath(value),
+            Self::HeadAngle => { head.angle = value; context.set_head(head); },
+            Self::HeadOffsetX => { head.offset_x = value; context.set_head(head); },
+            Self::HeadOffsetY => { head.offset_y = value; context.set_head(head); },
+            Self::HeadScale => { head.scale = value; context.set_head(head); },
+        }
+    }
+}
+
+/// Drives a parameter through `keyframes`, which must be sorted by time. Updates the parameter every frame while playing.
+#[derive(Clone, Debug)]
+pub struct TweenAnimator<'k, Target> {
+    target: Target,
+    keyframes: &'k [Keyframe],
+    mode: TweenMode,
+    origin: Option<Instant>,
+    is_finished: bool,
+}
+
+impl<'k, Target> TweenAnimator<'k, Target> {
+    /// Interval at which a finished tween is polled.
+    const IDLE_MILLISECONDS: u64 = 1000;
+
+    pub fn new(target: Target, keyframes: &'k [Keyframe], mode: TweenMode) -> Self {
+        Self {
+            target,
+            keyframes,
+            mode,
+            origin: None,
+            is_finished: false,
+        }
+    }
+    /// Returns true once a tween in `TweenMode::Once` has reached its last keyframe.
+    pub fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+    /// Starts the tween again from the first keyframe at the next frame.
+    pub fn restart(&mut self) {
+        self.origin = None;
+        self.is_finished = false;
+    }
+    pub fn duration_milliseconds(&self) -> u32 {
+        self.keyframes.last().map_or(0, |keyframe| keyframe.time_milliseconds)
+    }
+    /// Value of the tween `elapsed` milliseconds after the start.
+    pub fn value_at(&self, elapsed: u32) -> Option<f32> {
+        let first = self.keyframes.first()?;
+        let duration = self.duration_milliseconds();
+        let time = match self.mode {
+            _ if duration == 0 => duration,
+            TweenMode::Once => elapsed.min(duration),
+            TweenMode::Loop => elapsed % duration,
+            TweenMode::PingPong => {
+                // Computed in u64 since a round trip of a tween longer than u32::MAX / 2 milliseconds overflows u32.
+                let (elapsed, duration) = (elapsed as u64, duration as u64);
+                let cycle = elapsed % (duration * 2);
+                (if cycle < duration { cycle } else { duration * 2 - cycle }) as u32
+            },
+        };
+        let Some(index) = self.keyframes.iter().position(|keyframe| keyframe.time_milliseconds >= time) else {
+            return self.keyframes.last().map(|keyframe| keyframe.value);
+        };
+        if index == 0 {
+            return Some(first.value);
+        }
+        let (from, to) = (&self.keyframes[index - 1], &self.keyframes[index]);
+        let span = to.time_milliseconds - from.time_milliseconds;
+        let progress = if span == 0 { 1.0 } else { (time - from.time_milliseconds) as f32 / span as f32 };
+        Some(from.value + (to.value - from.value) * to.easing.apply(progress))
+    }
+}
+
+impl<'k, Context, Target: TweenTarget<Context>> Animator<Context> for TweenAnimator<'k, Target> {
+    fn next(&mut self, counter: FrameCounter, context: &mut Context) -> FrameCounter {
+        if self.is_finished {
+            return counter.after_milliseconds(Self::IDLE_MILLISECONDS);
+        }
+        let origin = *self.origin.get_or_insert(counter.time());
+        let elapsed = counter.time().milliseconds_since(&origin);
+        if let Some(value) = self.value_at(elapsed) {
+            self.target.set(context, value);
+        }
+        if self.mode == TweenMode::Once && elapsed >= self.duration_milliseconds() {
+            self.is_finished = true;
+            return counter.after_milliseconds(Self::IDLE_MILLISECONDS);
+        }
+        counter.after_frames(1)
+    }
+    fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+    fn reset(&mut self) {
+        self.restart();
+    }
+}
+
+impl<'k, Target> FaceControl for TweenAnimator<'k, Target> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ping_pong_longer_than_half_of_u32_does_not_overflow() {
+        let keyframes = [Keyframe::new(0, 0.0, Easing::Linear), Keyframe::new(u32::MAX, 1.0, Easing::Linear)];
+        let tween = TweenAnimator::new((), &keyframes, TweenMode::PingPong);
+        assert_eq!(tween.value_at(u32::MAX), Some(1.0));
+        assert_eq!(tween.value_at(0), Some(0.0));
+    }
+}
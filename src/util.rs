@@ -1,4 +1,5 @@
-use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
 use embedded_graphics::{prelude::{Point, Size, PixelColor}, primitives::{Circle, Ellipse, Rectangle}};
 #[allow(unused)]
 use micromath::F32Ext as _;
@@ -11,6 +12,7 @@ pub fn make_point_f32_rounded(x: f32, y: f32) -> Point {
         y.round() as i32,
     )
 }
+#[allow(dead_code)]
 pub fn make_size_f32_rounded(x: f32, y: f32) -> Size {
     Size::new(
         x.round() as u32,
@@ -41,10 +43,28 @@ pub fn rectangle_union(r1: &Rectangle, r2: &Rectangle) -> Rectangle {
             return Rectangle::zero();
         },
     };
-    let size = Size::new((bottom_right.x - top_left.x) as u32, (bottom_right.y - top_left.y) as u32);
+    let size = Size::new((bottom_right.x - top_left.x + 1) as u32, (bottom_right.y - top_left.y + 1) as u32);
     Rectangle::new(top_left, size)
 }
 
+/// Splits the part of `r1` outside of `r2` into up to four rectangles. Unused entries are zero sized.
+pub fn rectangle_difference(r1: &Rectangle, r2: &Rectangle) -> [Rectangle; 4] {
+    let intersection = r1.intersection(r2);
+    let (Some(bottom_right), Some(inner_bottom_right)) = (r1.bottom_right(), intersection.bottom_right()) else {
+        return [*r1, Rectangle::zero(), Rectangle::zero(), Rectangle::zero()];
+    };
+    let top_left = r1.top_left;
+    let inner_top_left = intersection.top_left;
+    let rows = |top: i32, bottom: i32| Rectangle::with_corners(Point::new(top_left.x, top), Point::new(bottom_right.x, bottom));
+    let columns = |left: i32, right: i32| Rectangle::with_corners(Point::new(left, inner_top_left.y), Point::new(right, inner_bottom_right.y));
+    [
+        if inner_top_left.y > top_left.y { rows(top_left.y, inner_top_left.y - 1) } else { Rectangle::zero() },
+        if inner_bottom_right.y < bottom_right.y { rows(inner_bottom_right.y + 1, bottom_right.y) } else { Rectangle::zero() },
+        if inner_top_left.x > top_left.x { columns(top_left.x, inner_top_left.x - 1) } else { Rectangle::zero() },
+        if inner_bottom_right.x < bottom_right.x { columns(inner_bottom_right.x + 1, bottom_right.x) } else { Rectangle::zero() },
+    ]
+}
+
 pub fn rectangle_union_all(rectangles: &[Rectangle]) -> Option<Rectangle> {
     let mut rectangle = None;
     for rect in rectangles {
@@ -57,8 +77,15 @@ pub fn rectangle_union_all(rectangles: &[Rectangle]) -> Option<Rectangle> {
     rectangle
 }
 
+/// Size of the scratch buffer required to render sprites for all of `rectangles` one at a time.
+pub fn scratch_size_for<C: PixelColor>(rectangles: &[Rectangle]) -> usize {
+    rectangles.iter()
+        .map(|r| Sprite::<C>::unaligned_buffer_size(r.size.width, r.size.height))
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "alloc")]
 pub fn prepare_sprite_buffer<C: PixelColor>(bounding_box: Rectangle) -> Vec<u8> {
-        let mut buffer = Vec::new();
-        buffer.resize(Sprite::<C>::unaligned_buffer_size(bounding_box.size.width, bounding_box.size.height), 0);
-        buffer
+        vec![0; Sprite::<C>::unaligned_buffer_size(bounding_box.size.width, bounding_box.size.height)]
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+use crate::Lerp;
+
+/// Mouth position of a group of speech sounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Viseme {
+    A,
+    I,
+    U,
+    E,
+    O,
+    /// Lips pressed together for N, M, B and P.
+    Closed,
+}
+
+impl Viseme {
+    pub const ALL: [Self; 6] = [Self::A, Self::I, Self::U, Self::E, Self::O, Self::Closed];
+}
+
+/// Continuous geometry of the mouth, which can be blended between two visemes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouthShape {
+    /// Width between the minimum, 0.0, and the maximum, 1.0, width of the mouth.
+    pub width: f32,
+    /// Height between the minimum, 0.0, and the maximum, 1.0, height of the mouth.
+    pub height: f32,
+    /// Rounds the corners, 0.0 for a rectangle and 1.0 for a pill.
+    pub roundness: f32,
+}
+
+impl MouthShape {
+    pub const CLOSED: Self = Self {
+        width: 0.7,
+        height: 0.0,
+        roundness: 0.0,
+    };
+
+    pub const fn of(viseme: Viseme) -> Self {
+        match viseme {
+            Viseme::A => Self { width: 0.55, height: 0.85, roundness: 0.5 },
+            Viseme::I => Self { width: 1.0, height: 0.2, roundness: 0.3 },
+            Viseme::U => Self { width: 0.0, height: 0.35, roundness: 1.0 },
+            Viseme::E => Self { width: 0.8, height: 0.45, roundness: 0.4 },
+            Viseme::O => Self { width: 0.2, height: 0.65, roundness: 1.0 },
+            Viseme::Closed => Self::CLOSED,
+        }
+    }
+    /// The rectangle which is drawn for `open_ratio` when no viseme is set. It narrows as it opens.
+    pub fn from_open_ratio(open_ratio: f32) -> Self {
+        Self {
+            width: 1.0 - open_ratio,
+            height: open_ratio,
+            roundness: 0.0,
+        }
+    }
+}
+
+impl Lerp for MouthShape {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            width: self.width.lerp(&other.width, t),
+            height: self.height.lerp(&other.height, t),
+            roundness: self.roundness.lerp(&other.roundness, t),
+        }
+    }
+}
+
+impl Default for MouthShape {
+    fn default() -> Self {
+        Self::CLOSED
+    }
+}
+
+impl From<Viseme> for MouthShape {
+    fn from(viseme: Viseme) -> Self {
+        Self::of(viseme)
+    }
+}